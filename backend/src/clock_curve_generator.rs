@@ -0,0 +1,228 @@
+use crate::games_info_generator::GameInfo;
+use crate::service_intermediary::GameFetchWarning;
+use crate::util;
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ClockCurvePoint {
+    pub move_number: usize,
+    pub user_clock: f32,     // seconds
+    pub opponent_clock: f32, // seconds
+    pub games_count: usize,  // Number of games that reached this move
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ClockCurvePly {
+    pub ply: usize,
+    pub user_clock: f32,     // seconds
+    pub opponent_clock: f32, // seconds
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct GameClockCurve {
+    pub game_number: usize,
+    pub plies: Vec<ClockCurvePly>,
+}
+
+#[derive(Default, Clone)]
+struct ClockCurveAccumulator {
+    user_clock_sum: i64,
+    opponent_clock_sum: i64,
+    games_count: usize,
+}
+
+// Clocks are recorded after each ply. Move n is complete once both players have played,
+// i.e after plies 2n - 2 (white) and 2n - 1 (black). As for the half time differential, the
+// clocks are berserk-adjusted.
+fn get_clocks_per_move(game_info: &GameInfo) -> Vec<(i64, i64)> {
+    let is_user_white = game_info.user_color == "white";
+
+    game_info
+        .timed_moves
+        .chunks_exact(2)
+        .map(|plies| {
+            let (white_clock, black_clock) = (plies[0].move_time, plies[1].move_time);
+            if is_user_white {
+                util::get_berserk_adjusted_clocks(game_info, white_clock, black_clock)
+            } else {
+                util::get_berserk_adjusted_clocks(game_info, black_clock, white_clock)
+            }
+        })
+        .collect()
+}
+
+// Only the clock of the player who just moved changes at each ply. Lichess does not run the
+// clocks for the first move of each player, so each clock starts from the first value recorded
+// for its player. The clocks are berserk-adjusted like the average curve.
+fn get_clocks_per_ply(game_info: &GameInfo) -> Vec<ClockCurvePly> {
+    let is_user_white = game_info.user_color == "white";
+    let white_initial_clock = match game_info.timed_moves.first() {
+        Some(timed_move) => timed_move.move_time,
+        None => return Vec::new(),
+    };
+    let black_initial_clock = game_info
+        .timed_moves
+        .get(1)
+        .map_or(white_initial_clock, |timed_move| timed_move.move_time);
+
+    let (mut user_clock, mut opponent_clock) = if is_user_white {
+        (white_initial_clock, black_initial_clock)
+    } else {
+        (black_initial_clock, white_initial_clock)
+    };
+    game_info
+        .timed_moves
+        .iter()
+        .enumerate()
+        .map(|(i, timed_move)| {
+            let is_white_move = i % 2 == 0;
+            if is_white_move == is_user_white {
+                user_clock = timed_move.move_time;
+            } else {
+                opponent_clock = timed_move.move_time;
+            }
+
+            let (adjusted_user_clock, adjusted_opponent_clock) =
+                util::get_berserk_adjusted_clocks(game_info, user_clock, opponent_clock);
+            ClockCurvePly {
+                ply: i + 1,
+                user_clock: util::convert_centiseconds_to_seconds(adjusted_user_clock as i32),
+                opponent_clock: util::convert_centiseconds_to_seconds(
+                    adjusted_opponent_clock as i32,
+                ),
+            }
+        })
+        .collect()
+}
+
+pub fn generate_per_game(
+    games: &[GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+) -> Vec<GameClockCurve> {
+    games
+        .iter()
+        .filter(|game_info| !util::is_game_skipped(game_info, skipped_games))
+        .map(|game_info| GameClockCurve {
+            game_number: game_info.game_index + 1,
+            plies: get_clocks_per_ply(game_info),
+        })
+        .collect()
+}
+
+pub fn generate_average(
+    games: &[GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+) -> Vec<ClockCurvePoint> {
+    let mut accumulators: Vec<ClockCurveAccumulator> = Vec::new();
+
+    for game_info in games.iter() {
+        if util::is_game_skipped(game_info, skipped_games) {
+            // The current game has already an internal error.
+            // Skip it from the computation.
+            continue;
+        }
+
+        let clocks_per_move = get_clocks_per_move(game_info);
+        if accumulators.len() < clocks_per_move.len() {
+            accumulators.resize(clocks_per_move.len(), ClockCurveAccumulator::default());
+        }
+
+        for (accumulator, (user_clock, opponent_clock)) in
+            accumulators.iter_mut().zip(clocks_per_move)
+        {
+            accumulator.user_clock_sum += user_clock;
+            accumulator.opponent_clock_sum += opponent_clock;
+            accumulator.games_count += 1;
+        }
+    }
+
+    accumulators
+        .into_iter()
+        .enumerate()
+        .map(|(i, accumulator)| ClockCurvePoint {
+            move_number: i + 1,
+            user_clock: util::convert_centiseconds_to_seconds(
+                (accumulator.user_clock_sum / accumulator.games_count as i64) as i32,
+            ),
+            opponent_clock: util::convert_centiseconds_to_seconds(
+                (accumulator.opponent_clock_sum / accumulator.games_count as i64) as i32,
+            ),
+            games_count: accumulator.games_count,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate_average() {
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let game_b = unit_test_util::get_some_mocked_game_b();
+        let input_games = vec![
+            games_info_generator::generate(&game_a, &0, "user").unwrap(),
+            games_info_generator::generate(&game_b, &1, "user").unwrap(),
+        ];
+
+        let clock_curve = generate_average(&input_games, &HashMap::new());
+        assert_eq!(clock_curve.len(), 3);
+
+        // Game a: user is black, game b: user is white.
+        assert_eq!(clock_curve[1].move_number, 2);
+        assert_eq!(clock_curve[1].games_count, 2);
+        assert_eq!(
+            clock_curve[1].user_clock,
+            util::convert_centiseconds_to_seconds((17931 + 17509) / 2)
+        );
+        assert_eq!(
+            clock_curve[1].opponent_clock,
+            util::convert_centiseconds_to_seconds((17939 + 16931) / 2)
+        );
+    }
+
+    #[test]
+    fn test_generate_per_game() {
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let input_games = vec![games_info_generator::generate(&game_a, &0, "user").unwrap()];
+
+        let clock_curves = generate_per_game(&input_games, &HashMap::new());
+        assert_eq!(clock_curves.len(), 1);
+        assert_eq!(clock_curves[0].game_number, 1);
+
+        // The user is black: the opponent's clock moves on odd plies.
+        let plies = &clock_curves[0].plies;
+        assert_eq!(plies.len(), 6);
+        assert_eq!(plies[2].ply, 3);
+        assert_eq!(plies[2].opponent_clock, 179.39);
+        assert_eq!(plies[2].user_clock, 180.03);
+        assert_eq!(plies[3].user_clock, 179.31);
+    }
+
+    #[test]
+    fn test_berserk_adjusted_clocks() {
+        // The user berserked: the 90 seconds given up are added back to their clock only.
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let mut game_info = games_info_generator::generate(&game_a, &0, "user").unwrap();
+        game_info.user_berserked = true;
+        game_info.berserk_time = 9000;
+        let input_games = vec![game_info];
+
+        let plies = &generate_per_game(&input_games, &HashMap::new())[0].plies;
+        assert_eq!(plies[2].opponent_clock, 179.39);
+        assert_eq!(plies[2].user_clock, 270.03);
+
+        let clock_curve = generate_average(&input_games, &HashMap::new());
+        assert_eq!(
+            clock_curve[1].user_clock,
+            util::convert_centiseconds_to_seconds(17931 + 9000)
+        );
+        assert_eq!(
+            clock_curve[1].opponent_clock,
+            util::convert_centiseconds_to_seconds(17939)
+        );
+    }
+}
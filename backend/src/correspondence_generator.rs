@@ -0,0 +1,133 @@
+use crate::games_info_generator::GameInfo;
+use crate::service_intermediary::GameFetchWarning;
+use crate::util;
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+const MILLISECONDS_PER_HOUR: f32 = 3_600_000.0;
+const HOURS_PER_DAY: f32 = 24.0;
+
+// Daily games have no clocks, their time usage is measured from the game timestamps instead.
+// Rates are None when no game qualifies for them.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CorrespondenceAnalysis {
+    pub games_count: usize,
+    pub average_days_per_turn: f32,
+    pub average_hours_per_move: Option<f32>, // Per ply, i.e. per turn of either player
+    pub allowance_used_rate: Option<f32>,    // Share of the days per turn taken by a move
+    pub user_timeout_count: usize,
+    pub opponent_timeout_count: usize,
+}
+
+// Lichess exports no time per move for daily games, the hours between the creation of the game
+// and its last move are spread over all of its plies. The turns of both players are averaged,
+// since the timestamps cannot tell them apart.
+fn get_hours_per_move(game_info: &GameInfo) -> Option<f32> {
+    let duration = game_info
+        .last_move_at?
+        .checked_sub(game_info.created_at?)
+        .filter(|_| game_info.plies_count > 0)?;
+    Some(duration as f32 / MILLISECONDS_PER_HOUR / game_info.plies_count as f32)
+}
+
+// Skipped games are left out, except for their lack of clocks, as in process_win_rate.
+pub fn generate(
+    games: &[GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+) -> Option<CorrespondenceAnalysis> {
+    let daily_games = games
+        .iter()
+        .filter(|game_info| {
+            skipped_games
+                .get(&game_info.game_index)
+                .is_none_or(|warning| *warning == GameFetchWarning::GameHasNoClocks)
+        })
+        .filter_map(|game_info| Some((game_info, game_info.days_per_turn?)))
+        .collect::<Vec<(&GameInfo, u32)>>();
+    if daily_games.is_empty() {
+        return None;
+    }
+
+    let days_per_turn = daily_games
+        .iter()
+        .map(|(_, days_per_turn)| *days_per_turn as f32)
+        .collect::<Vec<f32>>();
+
+    let (hours_per_move, allowance_used_rates): (Vec<f32>, Vec<f32>) = daily_games
+        .iter()
+        .filter_map(|(game_info, days_per_turn)| {
+            let hours_per_move = get_hours_per_move(game_info)?;
+            Some((
+                hours_per_move,
+                hours_per_move / (*days_per_turn as f32 * HOURS_PER_DAY),
+            ))
+        })
+        .unzip();
+
+    let (mut user_timeout_count, mut opponent_timeout_count) = (0, 0);
+    for (game_info, _) in daily_games.iter() {
+        if util::is_game_draw(game_info) || game_info.game_status != "outoftime" {
+            continue;
+        }
+        if util::has_user_won_game(game_info) {
+            opponent_timeout_count += 1;
+        } else {
+            user_timeout_count += 1;
+        }
+    }
+
+    Some(CorrespondenceAnalysis {
+        games_count: daily_games.len(),
+        average_days_per_turn: util::compute_average(&days_per_turn),
+        average_hours_per_move: (!hours_per_move.is_empty())
+            .then(|| util::compute_average(&hours_per_move)),
+        allowance_used_rate: (!allowance_used_rates.is_empty())
+            .then(|| util::compute_average(&allowance_used_rates)),
+        user_timeout_count,
+        opponent_timeout_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate() {
+        // 40 plies played over 5 days with 3 days per move, lost on time by the user.
+        let game = unit_test_util::get_some_mocked_correspondence_game(3);
+        let mut game_info = games_info_generator::generate(&game, &0, "user").unwrap();
+        assert_eq!(game_info.time_control, "1/259200");
+        assert!(game_info.timed_moves.is_empty());
+        game_info.game_status = String::from("outoftime");
+
+        // Clock games are left out.
+        let clock_game = unit_test_util::get_some_mocked_game_a();
+        let games = [
+            game_info,
+            games_info_generator::generate(&clock_game, &1, "user").unwrap(),
+        ];
+
+        let analysis = generate(&games, &HashMap::new()).unwrap();
+        assert_eq!(analysis.games_count, 1);
+        assert_eq!(analysis.average_days_per_turn, 3.0);
+        assert_eq!(analysis.average_hours_per_move, Some(3.0));
+        assert_eq!(analysis.allowance_used_rate, Some(3.0 / 72.0));
+        assert_eq!(analysis.user_timeout_count, 1);
+        assert_eq!(analysis.opponent_timeout_count, 0);
+
+        // Games skipped for their lack of clocks only are kept, the excluded ones are not.
+        let mut skipped_games = HashMap::new();
+        skipped_games.insert(0, GameFetchWarning::GameHasNoClocks);
+        assert_eq!(generate(&games, &skipped_games), Some(analysis));
+        for warning in [
+            GameFetchWarning::InternalErrorOccuredWhileProcessingAGame,
+            GameFetchWarning::ComputerOpponent,
+        ] {
+            skipped_games.insert(0, warning);
+            assert_eq!(generate(&games, &skipped_games), None);
+        }
+    }
+}
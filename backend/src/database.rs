@@ -1,7 +1,12 @@
+use crate::progress_generator::AnalysisSnapshot;
+
 use rusqlite::{params, Connection, Result, ToSql};
 
-pub fn create_database() -> Result<()> {
-    let connection = Connection::open("request_timing_data.db")?;
+// The path is given by the app state, tests work on their own database, see TestDatabase.
+pub const DATABASE_PATH: &str = "request_timing_data.db";
+
+pub fn create_database(database_path: &str) -> Result<()> {
+    let connection = Connection::open(database_path)?;
 
     connection.execute(
         "CREATE TABLE IF NOT EXISTS request_logs (
@@ -14,17 +19,38 @@ pub fn create_database() -> Result<()> {
         )",
         [],
     )?;
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS analysis_snapshots (
+            id INTEGER PRIMARY KEY,
+            username TEXT,
+            created_at INTEGER,
+            perf_type TEXT,
+            user_color TEXT,
+            games_count INTEGER,
+            analysed_games_count INTEGER,
+            average_time REAL,
+            win_rate REAL,
+            user_flag_rate REAL,
+            opponent_flag_rate REAL,
+            consistency_score REAL
+        )",
+        [],
+    )?;
+    // The consistency scores are kept with the other metrics of each analysis in
+    // analysis_snapshots, the table that held them on their own is no longer read.
+    connection.execute("DROP TABLE IF EXISTS consistency_scores", [])?;
     Ok(())
 }
 
 pub fn log_request_data(
+    database_path: &str,
     games_count: i32,
     game_mode: &str,
     user_color: &str,
     user_elo: Option<i32>,
     processing_time: f32,
 ) -> Result<()> {
-    let conn = Connection::open("request_timing_data.db")?;
+    let conn = Connection::open(database_path)?;
 
     conn.execute(
         "INSERT INTO request_logs (games_count, game_mode, user_color, user_elo, processing_time) VALUES (?1, ?2, ?3, ?4, ?5)",
@@ -38,3 +64,89 @@ pub fn log_request_data(
     )?;
     Ok(())
 }
+
+// Usernames are stored in lowercase, lichess ignores their case. Snapshots come in chronological
+// order.
+pub fn get_analysis_snapshots(
+    database_path: &str,
+    username: &str,
+    perf_type: &str,
+) -> Result<Vec<AnalysisSnapshot>> {
+    let conn = Connection::open(database_path)?;
+
+    let mut statement = conn.prepare(
+        "SELECT created_at, perf_type, user_color, games_count, analysed_games_count, \
+         average_time, win_rate, user_flag_rate, opponent_flag_rate, consistency_score \
+         FROM analysis_snapshots WHERE username = ?1 AND perf_type = ?2 ORDER BY created_at, id",
+    )?;
+    let snapshots = statement
+        .query_map(params![username.to_lowercase(), perf_type], |row| {
+            Ok(AnalysisSnapshot {
+                created_at: row.get(0)?,
+                perf_type: row.get(1)?,
+                user_color: row.get(2)?,
+                games_count: row.get(3)?,
+                analysed_games_count: row.get(4)?,
+                average_time: row.get(5)?,
+                win_rate: row.get(6)?,
+                user_flag_rate: row.get(7)?,
+                opponent_flag_rate: row.get(8)?,
+                consistency_score: row.get(9)?,
+            })
+        })?
+        .collect();
+    snapshots
+}
+
+pub fn log_analysis_snapshot(
+    database_path: &str,
+    username: &str,
+    snapshot: &AnalysisSnapshot,
+) -> Result<()> {
+    let conn = Connection::open(database_path)?;
+
+    conn.execute(
+        "INSERT INTO analysis_snapshots (username, created_at, perf_type, user_color, games_count, \
+         analysed_games_count, average_time, win_rate, user_flag_rate, opponent_flag_rate, \
+         consistency_score) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            username.to_lowercase(),
+            snapshot.created_at,
+            snapshot.perf_type,
+            snapshot.user_color,
+            snapshot.games_count,
+            snapshot.analysed_games_count,
+            snapshot.average_time,
+            snapshot.win_rate,
+            snapshot.user_flag_rate,
+            snapshot.opponent_flag_rate,
+            snapshot.consistency_score
+        ],
+    )?;
+    Ok(())
+}
+
+// Scratch database of a test, removed once the test is over.
+#[cfg(test)]
+pub struct TestDatabase {
+    pub path: String,
+}
+
+#[cfg(test)]
+impl TestDatabase {
+    pub fn create() -> Self {
+        let path = std::env::temp_dir()
+            .join(format!("request_timing_data_{}.db", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        create_database(&path).unwrap();
+        TestDatabase { path }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
@@ -1,14 +1,18 @@
 use std::collections::HashMap;
 
+use crate::localization::{Locale, Message};
 use crate::service_intermediary::GameFetchWarning;
 
 use serde::{Deserialize, Serialize};
 
+// Lichess serializes its fields in camelCase, e.g "createdAt" or "ratingDiff".
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct GameJson {
     pub clock: Option<Clock>,
     pub clocks: Option<Vec<i64>>,
     pub created_at: Option<u64>,
+    pub days_per_turn: Option<u32>, // Correspondence games only, they have no clock
     pub id: Option<String>,
     pub last_move_at: Option<u64>,
     pub moves: Option<String>,
@@ -16,7 +20,8 @@ pub struct GameJson {
     pub players: Option<Players>,
     pub rated: Option<bool>,
     pub speed: Option<String>,
-    pub status: Option<String>, // draw, mate, resign
+    pub status: Option<String>,     // draw, mate, resign
+    pub tournament: Option<String>, // Id of the arena tournament the game was played in
     pub variant: Option<String>,
     pub winner: Option<String>,
     #[serde(flatten)]
@@ -24,6 +29,7 @@ pub struct GameJson {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct Clock {
     pub increment: Option<i32>,
     pub initial: Option<i32>,
@@ -37,7 +43,10 @@ pub struct Players {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct PlayerDetail {
+    pub ai_level: Option<u8>, // Stockfish opponents have a level instead of a user
+    pub berserk: Option<bool>, // Arena games only, the player gave up half of the clock
     pub rating: Option<i32>,
     pub rating_diff: Option<i32>,
     pub user: Option<User>,
@@ -47,34 +56,39 @@ pub struct PlayerDetail {
 pub struct User {
     pub id: Option<String>,
     pub name: Option<String>,
+    pub title: Option<String>, // e.g "GM", or "BOT" for the bot accounts
+}
+
+pub fn convert_warning_to_displayable_format(warning: &GameFetchWarning, locale: Locale) -> String {
+    let message = match warning {
+        GameFetchWarning::GameHasNotEnoughMoves => Message::GameHasNotEnoughMoves,
+        GameFetchWarning::GameHasNoClocks => Message::GameHasNoClocks,
+        GameFetchWarning::GameHasMissingClocks => Message::GameHasMissingClocks,
+        GameFetchWarning::GameWasAborted => Message::GameWasAborted,
+        GameFetchWarning::AnonymousOpponent => Message::AnonymousOpponent,
+        GameFetchWarning::ComputerOpponent => Message::ComputerOpponent,
+        GameFetchWarning::CasualGame => Message::CasualGame,
+        GameFetchWarning::TournamentGame => Message::TournamentGame,
+        GameFetchWarning::NotATournamentGame => Message::NotATournamentGame,
+        GameFetchWarning::InternalErrorOccuredWhileProcessingAGame => Message::InternalErrorInGame,
+    };
+    message.localize(locale)
 }
 
 pub fn convert_games_with_errors_to_displayable_format(
     games_with_errors: HashMap<usize, GameFetchWarning>,
+    locale: Locale,
 ) -> Vec<(usize, String)> {
     if games_with_errors.is_empty() {
         return Vec::new();
     }
 
-    let mut enum_conversion_map: HashMap<GameFetchWarning, String> = HashMap::new();
-    enum_conversion_map.insert(
-        GameFetchWarning::GameHasNotEnoughMoves,
-        String::from("Game does not have enough moves."),
-    );
-    enum_conversion_map.insert(
-        GameFetchWarning::InternalErrorOccuredWhileProcessingAGame,
-        String::from("An internal error occured while processing this game."),
-    );
-
     let mut converted_errors = games_with_errors
         .into_iter()
         .map(|(i, warning_enum)| {
             (
                 i + 1, // Initially we enter the game index, we wish to display in non-indexed format.
-                enum_conversion_map
-                    .get(&warning_enum)
-                    .expect("Warning enum not found")
-                    .to_string(),
+                convert_warning_to_displayable_format(&warning_enum, locale),
             )
         })
         .collect::<Vec<(usize, String)>>();
@@ -85,6 +99,6 @@ pub fn convert_games_with_errors_to_displayable_format(
         "Game index should start from 1."
     );
 
-    converted_errors.sort_by_key(|k| k.0.clone());
+    converted_errors.sort_by_key(|k| k.0);
     converted_errors
 }
@@ -1,64 +1,234 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
-use serde::Serialize;
+use crate::localization::{self, Locale, Message};
+use crate::service_intermediary::GlobalFetchError;
 
-const S_FETCH_ERROR_: &str =
-    "There was a problem fetching the data. Please check your internet connection.";
-const S_INTERNAL_ERROR_: &str =
-    "There was an internal problem with the server. Please try again later.";
-const S_DATA_ERROR_: &str = "There was a problem processing the data.";
+use actix_web::{error::InternalError, http::StatusCode, HttpRequest, HttpResponse, ResponseError};
+use serde::Serialize;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ProcessError {
-    FetchError { message: String },
-    DataError { message: String }, // Maybe unused given that I still want to output results
-    InternalError { message: String },
+    FetchError { message: Message },
+    DataError { message: Message }, // Maybe unused given that I still want to output results
+    InternalError { message: Message },
+    GlobalFetchError { error: GlobalFetchError },
+}
+
+// Machine-readable counterpart of the displayed message, stable across locales.
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    UserNotFound,
+    RateLimited,
+    LichessUnavailable,
+    FetchFailed,
+    MalformedRequest,
+    DataProcessingFailed,
+    #[cfg_attr(feature = "png", allow(dead_code))] // Only raised without the png feature.
+    UnsupportedChartFormat,
+    RequestedMoreGamesThanAvailable,
+    NotEnoughGamesToComputeAverage,
+    StreamResumptionFailed,
+    DatabaseFailure,
+    InternalFailure,
 }
 
 #[derive(Serialize)]
 struct ErrorResponse {
+    code: ErrorCode,
     error: String,
 }
 
 impl ResponseError for ProcessError {
     fn status_code(&self) -> StatusCode {
-        match *self {
-            ProcessError::FetchError { .. } => StatusCode::SERVICE_UNAVAILABLE,
-            ProcessError::DataError { .. } => StatusCode::BAD_REQUEST,
-            ProcessError::InternalError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        match self.get_code() {
+            ErrorCode::UserNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::LichessUnavailable | ErrorCode::StreamResumptionFailed => {
+                StatusCode::BAD_GATEWAY
+            }
+            ErrorCode::FetchFailed => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::MalformedRequest | ErrorCode::DataProcessingFailed => {
+                StatusCode::BAD_REQUEST
+            }
+            // The request is valid, the server was built without the rasterizer.
+            ErrorCode::UnsupportedChartFormat => StatusCode::NOT_IMPLEMENTED,
+            ErrorCode::RequestedMoreGamesThanAvailable
+            | ErrorCode::NotEnoughGamesToComputeAverage => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::DatabaseFailure | ErrorCode::InternalFailure => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         }
     }
 
     fn error_response(&self) -> HttpResponse {
+        self.localized_error_response(Locale::default())
+    }
+}
+
+impl ProcessError {
+    fn get_message(&self) -> Message {
+        match self {
+            ProcessError::FetchError { message }
+            | ProcessError::DataError { message }
+            | ProcessError::InternalError { message } => message.clone(),
+            ProcessError::GlobalFetchError { error } => match error {
+                GlobalFetchError::RequestedMoreGamesThanAvailableInTheUserDatabase => {
+                    Message::RequestedMoreGamesThanAvailable
+                }
+                GlobalFetchError::NotEnoughGamesToComputeAverage => {
+                    Message::NotEnoughGamesToComputeAverage
+                }
+                GlobalFetchError::StreamResumptionFailed => Message::StreamResumptionFailed,
+            },
+        }
+    }
+
+    // Messages with a dedicated code, the others fall back to the code of their variant.
+    pub fn get_code(&self) -> ErrorCode {
+        match self {
+            ProcessError::FetchError { message } => match message {
+                Message::UserNotFound { .. } => ErrorCode::UserNotFound,
+                Message::RateLimited => ErrorCode::RateLimited,
+                Message::LichessUnavailable => ErrorCode::LichessUnavailable,
+                _ => ErrorCode::FetchFailed,
+            },
+            ProcessError::DataError { message } => match message {
+                Message::DataProcessingFailed => ErrorCode::DataProcessingFailed,
+                #[cfg(not(feature = "png"))]
+                Message::PngChartsDisabled => ErrorCode::UnsupportedChartFormat,
+                _ => ErrorCode::MalformedRequest,
+            },
+            ProcessError::InternalError { message } => match message {
+                Message::DatabaseFailure => ErrorCode::DatabaseFailure,
+                _ => ErrorCode::InternalFailure,
+            },
+            ProcessError::GlobalFetchError { error } => match error {
+                GlobalFetchError::RequestedMoreGamesThanAvailableInTheUserDatabase => {
+                    ErrorCode::RequestedMoreGamesThanAvailable
+                }
+                GlobalFetchError::NotEnoughGamesToComputeAverage => {
+                    ErrorCode::NotEnoughGamesToComputeAverage
+                }
+                GlobalFetchError::StreamResumptionFailed => ErrorCode::StreamResumptionFailed,
+            },
+        }
+    }
+
+    pub fn localized_error_response(&self, locale: Locale) -> HttpResponse {
         HttpResponse::build(self.status_code()).json(ErrorResponse {
-            error: self.to_string(),
+            code: self.get_code(),
+            error: self.get_message().localize(locale),
         })
     }
 }
 
+// Errors raised while processing a request are translated when they come from this crate. Any
+// other error is reported as an internal failure so that the response shape stays the same.
+pub fn get_localized_error_response(error: &actix_web::Error, locale: Locale) -> HttpResponse {
+    match error.as_error::<ProcessError>() {
+        Some(process_error) => process_error.localized_error_response(locale),
+        None => ProcessError::InternalError {
+            message: Message::InternalFailure,
+        }
+        .localized_error_response(locale),
+    }
+}
+
+// Error handler of the body, query and path extractors, run before the request reaches an
+// endpoint, e.g a missing username or an unknown chart kind.
+pub fn handle_malformed_request<E>(error: E, req: &HttpRequest) -> actix_web::Error
+where
+    E: std::fmt::Debug + std::fmt::Display + 'static,
+{
+    let process_error = ProcessError::DataError {
+        message: Message::MalformedRequest {
+            details: error.to_string(),
+        },
+    };
+    let response = process_error.localized_error_response(localization::resolve_locale(None, req));
+    InternalError::from_response(error, response).into()
+}
+
 impl From<reqwest::Error> for ProcessError {
-    fn from(e: reqwest::Error) -> Self {
+    fn from(_: reqwest::Error) -> Self {
         ProcessError::FetchError {
-            message: S_FETCH_ERROR_.into(),
+            message: Message::FetchFailed,
         }
     }
 }
 
 impl From<serde_json::Error> for ProcessError {
-    fn from(e: serde_json::Error) -> Self {
+    fn from(_: serde_json::Error) -> Self {
         ProcessError::DataError {
-            message: S_DATA_ERROR_.into(),
+            message: Message::DataProcessingFailed,
+        }
+    }
+}
+
+impl From<std::fmt::Error> for ProcessError {
+    fn from(_: std::fmt::Error) -> Self {
+        ProcessError::InternalError {
+            message: Message::InternalFailure,
         }
     }
 }
 
 impl std::fmt::Display for ProcessError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            ProcessError::FetchError { ref message }
-            | ProcessError::DataError { ref message }
-            | ProcessError::InternalError { ref message } => write!(f, "{}", message),
-        }
+        write!(f, "{}", self.get_message().localize(Locale::default()))
     }
 }
 
 impl std::error::Error for ProcessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_code() {
+        let user_not_found = ProcessError::FetchError {
+            message: Message::UserNotFound {
+                username: String::from("user"),
+            },
+        };
+        assert_eq!(user_not_found.get_code(), ErrorCode::UserNotFound);
+        assert_eq!(user_not_found.status_code(), StatusCode::NOT_FOUND);
+
+        let timezone_error = ProcessError::DataError {
+            message: Message::UnknownTimezone {
+                timezone: String::from("Mars/Olympus_Mons"),
+            },
+        };
+        assert_eq!(timezone_error.get_code(), ErrorCode::MalformedRequest);
+        assert_eq!(timezone_error.status_code(), StatusCode::BAD_REQUEST);
+
+        let not_enough_games = ProcessError::GlobalFetchError {
+            error: GlobalFetchError::NotEnoughGamesToComputeAverage,
+        };
+        assert_eq!(
+            not_enough_games.get_code(),
+            ErrorCode::NotEnoughGamesToComputeAverage
+        );
+        assert_eq!(
+            not_enough_games.status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[cfg(not(feature = "png"))]
+    #[test]
+    fn test_get_code_png_charts_disabled() {
+        let png_charts_disabled = ProcessError::DataError {
+            message: Message::PngChartsDisabled,
+        };
+        assert_eq!(
+            png_charts_disabled.get_code(),
+            ErrorCode::UnsupportedChartFormat
+        );
+        assert_eq!(
+            png_charts_disabled.status_code(),
+            StatusCode::NOT_IMPLEMENTED
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::deserialization;
+use crate::errors_manager::ProcessError;
+use crate::games_info_generator::GameInfo;
+use crate::localization::Locale;
+use crate::service_intermediary::{ExportFormat, GameFetchWarning};
+use crate::util;
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const CSV_HEADER: [&str; 13] = [
+    "game_number",
+    "game_id",
+    "date",
+    "user_color",
+    "opponent_username",
+    "user_rating",
+    "opponent_rating",
+    "time_control",
+    "variant",
+    "half_time_differential",
+    "result",
+    "game_status",
+    "skip_reason",
+];
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ExportedGameRow {
+    pub game_number: usize,
+    pub game_id: String,
+    pub date: Option<String>,
+    pub user_color: String,
+    pub opponent_username: String,
+    pub user_rating: i32,
+    pub opponent_rating: i32,
+    pub time_control: String,
+    pub variant: String,
+    pub half_time_differential: Option<f32>,
+    pub result: String,
+    pub game_status: String,
+    pub skip_reason: Option<String>,
+}
+
+impl ExportedGameRow {
+    fn to_csv_fields(&self) -> [String; 13] {
+        [
+            self.game_number.to_string(),
+            self.game_id.clone(),
+            self.date.clone().unwrap_or_default(),
+            self.user_color.clone(),
+            self.opponent_username.clone(),
+            self.user_rating.to_string(),
+            self.opponent_rating.to_string(),
+            self.time_control.clone(),
+            self.variant.clone(),
+            self.half_time_differential
+                .map(|differential| differential.to_string())
+                .unwrap_or_default(),
+            self.result.clone(),
+            self.game_status.clone(),
+            self.skip_reason.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+fn get_displayable_game_result(game_info: &GameInfo) -> String {
+    if util::is_game_draw(game_info) {
+        String::from("draw")
+    } else if util::has_user_won_game(game_info) {
+        String::from("win")
+    } else {
+        String::from("loss")
+    }
+}
+
+// Fields are quoted only when they contain a delimiter, a quote or a line break (RFC 4180).
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn generate(
+    games: &[GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+    half_time_differentials: &[f32],
+    locale: Locale,
+) -> Vec<ExportedGameRow> {
+    let mut differentials_iter = half_time_differentials.iter();
+
+    games
+        .iter()
+        .map(|game_info| {
+            // Skipped games have no half time differential. The differentials are stored
+            // contiguously for the games that were kept in the computation.
+            let skip_reason = skipped_games.get(&game_info.game_index).map(|warning| {
+                deserialization::convert_warning_to_displayable_format(warning, locale)
+            });
+            let half_time_differential = match skip_reason {
+                Some(_) => None,
+                None => differentials_iter.next().copied(),
+            };
+
+            ExportedGameRow {
+                game_number: game_info.game_index + 1,
+                game_id: game_info.game_id.clone(),
+                date: game_info
+                    .created_at
+                    .map(util::format_timestamp_as_utc_datetime),
+                user_color: game_info.user_color.clone(),
+                opponent_username: game_info.opponent_username.clone(),
+                user_rating: game_info.user_rating,
+                opponent_rating: game_info.opponent_rating,
+                time_control: game_info.time_control.clone(),
+                variant: game_info.variant.get_name(),
+                half_time_differential,
+                result: get_displayable_game_result(game_info),
+                game_status: game_info.game_status.clone(),
+                skip_reason,
+            }
+        })
+        .collect()
+}
+
+pub fn to_csv(rows: &[ExportedGameRow]) -> Result<String, ProcessError> {
+    let mut csv = String::new();
+    writeln!(csv, "{}", CSV_HEADER.join(","))?;
+
+    for row in rows {
+        let fields = row
+            .to_csv_fields()
+            .iter()
+            .map(|field| escape_csv_field(field))
+            .collect::<Vec<String>>();
+        writeln!(csv, "{}", fields.join(","))?;
+    }
+
+    Ok(csv)
+}
+
+pub fn to_ndjson(rows: &[ExportedGameRow]) -> Result<String, ProcessError> {
+    let mut ndjson = String::new();
+    for row in rows {
+        writeln!(ndjson, "{}", serde_json::to_string(row)?)?;
+    }
+
+    Ok(ndjson)
+}
+
+pub fn serialize(rows: &[ExportedGameRow], format: ExportFormat) -> Result<String, ProcessError> {
+    match format {
+        ExportFormat::Csv => to_csv(rows),
+        ExportFormat::Ndjson => to_ndjson(rows),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    fn make_exported_rows() -> Vec<ExportedGameRow> {
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let game_b = unit_test_util::get_some_mocked_game_b();
+        let input_games = vec![
+            games_info_generator::generate(&game_a, &0, "user").unwrap(),
+            games_info_generator::generate(&game_b, &1, "user").unwrap(),
+        ];
+
+        let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
+        skipped_games.insert(0, GameFetchWarning::GameHasNotEnoughMoves);
+
+        generate(&input_games, &skipped_games, &[5.78], Locale::En)
+    }
+
+    #[test]
+    fn test_generate() {
+        let rows = make_exported_rows();
+        assert_eq!(rows.len(), 2);
+
+        // Skipped game keeps its metadata but has no differential.
+        assert_eq!(rows[0].game_number, 1);
+        assert_eq!(rows[0].user_color, "black");
+        assert_eq!(rows[0].result, "loss");
+        assert_eq!(rows[0].half_time_differential, None);
+        assert_eq!(
+            rows[0].skip_reason.as_deref(),
+            Some("Game does not have enough moves.")
+        );
+
+        assert_eq!(rows[1].game_number, 2);
+        assert_eq!(rows[1].user_color, "white");
+        assert_eq!(rows[1].opponent_username, "other_user");
+        assert_eq!(rows[1].time_control, "180+0");
+        assert_eq!(rows[1].date.as_deref(), Some("2022-12-30T03:33:05Z"));
+        assert_eq!(rows[1].half_time_differential, Some(5.78));
+        assert_eq!(rows[1].skip_reason, None);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let csv = to_csv(&make_exported_rows()).unwrap();
+        let lines = csv.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert_eq!(
+            lines[2],
+            "2,9kZXlH2K,2022-12-30T03:33:05Z,white,other_user,2000,2054,180+0,standard,5.78,loss,mate,"
+        );
+        assert_eq!(escape_csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_to_ndjson() {
+        let ndjson = to_ndjson(&make_exported_rows()).unwrap();
+        let lines = ndjson.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 2);
+        let second_row: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second_row["game_id"], "9kZXlH2K");
+        assert_eq!(second_row["skip_reason"], serde_json::Value::Null);
+    }
+}
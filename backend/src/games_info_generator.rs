@@ -1,6 +1,10 @@
 use serde::Serialize;
 
-use crate::deserialization::GameJson;
+use crate::deserialization::{GameJson, PlayerDetail};
+use crate::service_intermediary::{ChessDataRequest, GameFetchWarning, TournamentGamesFilter};
+use crate::variant::Variant;
+
+const SECONDS_PER_DAY: u32 = 86400;
 
 #[derive(Clone, Debug, Serialize)]
 pub struct TimedMove {
@@ -8,104 +12,223 @@ pub struct TimedMove {
     pub move_time: i64,
 }
 
+// Lichess bots are accounts run by an engine, they can play rated games. The lichess AI
+// (Stockfish) and anonymous players only play casual games and have no rating.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum OpponentKind {
+    Human,
+    Bot,
+    Computer,
+    Anonymous,
+}
+
+impl OpponentKind {
+    pub fn has_rating(&self) -> bool {
+        matches!(self, OpponentKind::Human | OpponentKind::Bot)
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct GameInfo {
     pub game_index: usize,
-    pub timed_moves: Vec<TimedMove>,
+    pub game_id: String,
+    pub variant: Variant,
+    pub created_at: Option<u64>,   // Unix timestamp in milliseconds
+    pub last_move_at: Option<u64>, // Unix timestamp in milliseconds
+    pub time_control: String,
+    pub increment: i64, // Centiseconds added after each move, except for a player who berserked
+    pub days_per_turn: Option<u32>,
+    pub plies_count: usize,          // Also known for the games without clocks
+    pub timed_moves: Vec<TimedMove>, // Empty for the games without clocks
     pub user_color: String,
     pub user_rating: i32,
+    pub user_rating_diff: Option<i32>,
     pub opponent_rating: i32,
     pub opponent_username: String,
+    pub opponent_kind: OpponentKind,
     pub winner_color: Option<String>, // If some then white or black, if none then draw
     pub game_status: String,
+    pub rated: bool,
+    pub tournament_id: Option<String>,
+    pub user_berserked: bool,
+    pub opponent_berserked: bool,
+    pub berserk_time: i64, // Centiseconds taken off the clock of a player who berserked
 }
 
-pub fn generate_timed_moves(game: &GameJson) -> Vec<TimedMove> {
-    let mut timed_moves: Vec<TimedMove> = Vec::new();
-
-    let moves: Vec<String> = game
-        .moves
-        .as_ref()
-        .unwrap()
-        .split(" ")
-        .map(|s| s.to_string())
-        .collect::<Vec<_>>();
+pub fn generate_timed_moves(game: &GameJson) -> Result<Vec<TimedMove>, GameFetchWarning> {
+    let moves: Vec<String> = match game.moves.as_ref() {
+        Some(moves) => moves
+            .split_whitespace() // Crazyhouse drops are written like any other move, e.g "N@f3"
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>(),
+        None => Vec::new(), // Lichess leaves the moves out when none was played
+    };
 
-    let mut clocks: Vec<i64> = game
-        .clocks
-        .as_ref()
-        .unwrap()
-        .iter()
-        .cloned()
-        .collect::<Vec<_>>();
+    let clocks: &[i64] = match (game.clocks.as_ref(), game.clock.as_ref()) {
+        (Some(clocks), _) => clocks,
+        // Correspondence games have no clocks, their moves cannot be timed.
+        (None, None) => return Ok(Vec::new()),
+        (None, Some(_)) => return Err(GameFetchWarning::GameHasMissingClocks),
+    };
 
-    if clocks.len() > moves.len() {
-        // if the last move in the game was a checkmate, the last
-        // clock stamp does not register.
-        clocks.truncate(clocks.len() - 1);
+    // Extra clock stamps are ignored, missing ones would leave moves without a time.
+    if clocks.len() < moves.len() {
+        return Err(GameFetchWarning::GameHasMissingClocks);
     }
 
-    for (i, x) in moves.iter().cloned().enumerate() {
-        timed_moves.push(TimedMove {
-            move_key: x,
-            move_time: clocks[i],
-        });
+    Ok(moves
+        .into_iter()
+        .zip(clocks.iter())
+        .map(|(move_key, move_time)| TimedMove {
+            move_key,
+            move_time: *move_time,
+        })
+        .collect())
+}
+
+fn get_player_detail<'a>(game: &'a GameJson, color: &str) -> Option<&'a PlayerDetail> {
+    let players = game.players.as_ref()?;
+    match color {
+        "black" => players.black.as_ref(),
+        _ => players.white.as_ref(),
     }
-    timed_moves
 }
 
-pub fn get_user_color(game: &GameJson, user_name: &str) -> String {
-    if let Some(players) = game.players.as_ref() {
-        if let Some(black_player) = players.black.as_ref() {
-            if let Some(user) = black_player.user.as_ref() {
-                if user.name.as_ref().unwrap().to_lowercase() == user_name.to_lowercase() {
-                    return "black".to_string();
-                }
-            }
+// The user is looked up on both sides, the other side can be the lichess AI or anonymous.
+pub fn get_user_color(game: &GameJson, user_name: &str) -> Result<String, GameFetchWarning> {
+    ["white", "black"]
+        .into_iter()
+        .find(|color| {
+            get_player_detail(game, color)
+                .and_then(|player_detail| player_detail.user.as_ref())
+                .and_then(|user| user.name.as_deref())
+                .is_some_and(|name| name.to_lowercase() == user_name.to_lowercase())
+        })
+        .map(str::to_string)
+        .ok_or(GameFetchWarning::InternalErrorOccuredWhileProcessingAGame)
+}
+
+// The lichess AI has a level instead of a user, anonymous players have neither.
+pub fn get_opponent_kind_and_username(
+    game: &GameJson,
+    opponent_color: &str,
+) -> Result<(OpponentKind, String), GameFetchWarning> {
+    let player_detail = get_player_detail(game, opponent_color)
+        .ok_or(GameFetchWarning::InternalErrorOccuredWhileProcessingAGame)?;
+    if let Some(ai_level) = player_detail.ai_level {
+        return Ok((
+            OpponentKind::Computer,
+            format!("Stockfish level {}", ai_level),
+        ));
+    }
+
+    match player_detail.user.as_ref() {
+        Some(user) => {
+            let opponent_kind = match user.title.as_deref() {
+                Some("BOT") => OpponentKind::Bot,
+                _ => OpponentKind::Human,
+            };
+            Ok((opponent_kind, user.name.clone().unwrap_or_default()))
         }
+        None => Ok((OpponentKind::Anonymous, String::from("Anonymous"))),
     }
-    "white".to_string()
 }
 
-pub fn get_opponent_username(game: &GameJson, opponent_color: &str) -> String {
-    let players = match game.players.as_ref() {
-        Some(players) => players,
-        None => return String::new(),
-    };
+pub fn get_user_rating(game: &GameJson, user_color: &str) -> i32 {
+    get_player_detail(game, user_color)
+        .and_then(|player_detail| player_detail.rating)
+        .unwrap_or(0)
+}
 
-    let player_detail = match opponent_color {
-        "black" => &players.black,
-        _ => &players.white,
-    };
+pub fn has_player_berserked(game: &GameJson, color: &str) -> bool {
+    get_player_detail(game, color)
+        .and_then(|player_detail| player_detail.berserk)
+        .unwrap_or(false)
+}
 
-    player_detail
-        .as_ref()
-        .unwrap()
-        .user
+pub fn get_increment(game: &GameJson) -> i64 {
+    game.clock
         .as_ref()
-        .unwrap()
-        .name
+        .and_then(|clock| clock.increment)
+        .map_or(0, |increment| increment as i64 * 100)
+}
+
+// Berserking halves the initial time of the player, in centiseconds like the clocks.
+pub fn get_berserk_time(game: &GameJson) -> i64 {
+    game.clock
         .as_ref()
-        .unwrap()
-        .to_string()
+        .and_then(|clock| clock.initial)
+        .map_or(0, |initial| initial as i64 * 100 / 2)
 }
 
-pub fn get_user_rating(game: &GameJson, user_color: &str) -> i32 {
-    let players = match game.players.as_ref() {
-        Some(players) => players,
-        None => return 0,
-    };
+pub fn get_user_rating_diff(game: &GameJson, user_color: &str) -> Option<i32> {
+    get_player_detail(game, user_color)?.rating_diff
+}
 
-    let player_detail = match user_color {
-        "black" => &players.black,
-        _ => &players.white,
-    };
+pub fn get_game_status(game: &GameJson) -> Result<String, GameFetchWarning> {
+    match game.status.as_deref() {
+        Some("aborted") => Err(GameFetchWarning::GameWasAborted),
+        Some(status) => Ok(status.to_string()),
+        None => Err(GameFetchWarning::InternalErrorOccuredWhileProcessingAGame),
+    }
+}
+
+pub fn get_game_id(game: &GameJson) -> String {
+    game.id.clone().unwrap_or_default()
+}
 
-    player_detail.as_ref().unwrap().rating.unwrap_or(0)
+pub fn get_plies_count(game: &GameJson) -> usize {
+    game.moves
+        .as_ref()
+        .map_or(0, |moves| moves.split_whitespace().count())
+}
+
+// Formatted like the PGN TimeControl tag, i.e "initial+increment" in seconds, or
+// "1/seconds" for the one move per period of correspondence games.
+pub fn get_time_control(game: &GameJson) -> String {
+    match (game.clock.as_ref(), game.days_per_turn) {
+        (Some(clock), _) => format!(
+            "{}+{}",
+            clock.initial.unwrap_or(0),
+            clock.increment.unwrap_or(0)
+        ),
+        (None, Some(days_per_turn)) => format!("1/{}", days_per_turn * SECONDS_PER_DAY),
+        (None, None) => String::from("-"),
+    }
 }
 
-pub fn get_game_status(game: &GameJson) -> String {
-    game.status.as_ref().unwrap().to_string()
+// Games against the lichess AI and anonymous players are left out unless the request includes
+// them, bot accounts play rated games and are kept. Including them drops the rated filter of the
+// lichess request, the casual games against players are then left out here. Tournament games are
+// kept or left out following the tournament filter.
+pub fn get_excluded_game_warning(
+    game_info: &GameInfo,
+    request_data: &ChessDataRequest,
+) -> Option<GameFetchWarning> {
+    let is_tournament_game = game_info.tournament_id.is_some();
+    match request_data.tournament_games.unwrap_or_default() {
+        TournamentGamesFilter::Only if !is_tournament_game => {
+            return Some(GameFetchWarning::NotATournamentGame)
+        }
+        TournamentGamesFilter::Exclude if is_tournament_game => {
+            return Some(GameFetchWarning::TournamentGame)
+        }
+        _ => {}
+    }
+
+    match game_info.opponent_kind {
+        OpponentKind::Computer if !request_data.include_computer_opponents.unwrap_or(false) => {
+            Some(GameFetchWarning::ComputerOpponent)
+        }
+        OpponentKind::Anonymous if !request_data.include_anonymous_opponents.unwrap_or(false) => {
+            Some(GameFetchWarning::AnonymousOpponent)
+        }
+        OpponentKind::Human | OpponentKind::Bot if !game_info.rated => {
+            Some(GameFetchWarning::CasualGame)
+        }
+        _ => None,
+    }
 }
 
 pub fn get_winner_color(game: &GameJson) -> Option<String> {
@@ -122,24 +245,178 @@ pub fn get_opponents_and_their_rating(games_considered: &[GameInfo]) -> Vec<(Str
         .collect()
 }
 
-pub fn generate(game: &GameJson, game_idx: &usize, user_name: &String) -> GameInfo {
-    let user_color = get_user_color(game, user_name);
+// Games that cannot be analysed are rejected with the warning to display instead.
+pub fn generate(
+    game: &GameJson,
+    game_idx: &usize,
+    user_name: &str,
+) -> Result<GameInfo, GameFetchWarning> {
+    let game_status = get_game_status(game)?;
+    let user_color = get_user_color(game, user_name)?;
     let user_rating = get_user_rating(game, &user_color);
+    let user_rating_diff = get_user_rating_diff(game, &user_color);
+    let user_berserked = has_player_berserked(game, &user_color);
     let opponent_color = if user_color == "black" {
         "white"
     } else {
         "black"
     };
     let opponent_rating = get_user_rating(game, opponent_color);
+    let (opponent_kind, opponent_username) = get_opponent_kind_and_username(game, opponent_color)?;
+    let timed_moves = generate_timed_moves(game)?;
 
-    GameInfo {
+    Ok(GameInfo {
         game_index: *game_idx,
-        timed_moves: generate_timed_moves(game),
-        user_color: user_color,
-        user_rating: user_rating,
-        opponent_rating: opponent_rating,
-        opponent_username: get_opponent_username(game, opponent_color),
+        game_id: get_game_id(game),
+        variant: Variant::from_game_variant(game.variant.as_deref()),
+        created_at: game.created_at,
+        last_move_at: game.last_move_at,
+        time_control: get_time_control(game),
+        increment: get_increment(game),
+        days_per_turn: game.days_per_turn,
+        plies_count: get_plies_count(game),
+        timed_moves,
+        user_color,
+        user_rating,
+        user_rating_diff,
+        opponent_rating,
+        opponent_username,
+        opponent_kind,
         winner_color: get_winner_color(game),
-        game_status: get_game_status(game),
+        game_status,
+        rated: game.rated.unwrap_or(true),
+        tournament_id: game.tournament.clone(),
+        user_berserked,
+        opponent_berserked: has_player_berserked(game, opponent_color),
+        berserk_time: get_berserk_time(game),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_test_util;
+
+    #[test]
+    fn test_generate_rejected_games() {
+        let generate_warning = |edit: fn(&mut GameJson)| {
+            let mut game = unit_test_util::get_some_mocked_complete_game(1000);
+            edit(&mut game);
+            generate(&game, &0, "user").err()
+        };
+
+        assert_eq!(generate_warning(|_| {}), None);
+        assert_eq!(
+            generate_warning(|game| game.status = Some("aborted".to_string())),
+            Some(GameFetchWarning::GameWasAborted)
+        );
+        assert_eq!(
+            generate_warning(|game| game.clocks = None),
+            Some(GameFetchWarning::GameHasMissingClocks)
+        );
+        assert_eq!(
+            generate_warning(|game| game.clocks.as_mut().unwrap().truncate(10)),
+            Some(GameFetchWarning::GameHasMissingClocks)
+        );
+        assert_eq!(
+            generate_warning(|game| game.players.as_mut().unwrap().black = None),
+            Some(GameFetchWarning::InternalErrorOccuredWhileProcessingAGame)
+        );
+        assert_eq!(
+            generate_warning(|game| game.status = None),
+            Some(GameFetchWarning::InternalErrorOccuredWhileProcessingAGame)
+        );
+    }
+
+    #[test]
+    fn test_get_opponent_kind_and_username() {
+        let get_opponent = |edit: fn(&mut PlayerDetail)| {
+            let mut game = unit_test_util::get_some_mocked_complete_game(1000);
+            edit(game.players.as_mut().unwrap().white.as_mut().unwrap());
+            let game_info = generate(&game, &0, "user").unwrap();
+            assert_eq!(game_info.user_color, "black");
+            (game_info.opponent_kind, game_info.opponent_username)
+        };
+
+        assert_eq!(
+            get_opponent(|_| {}),
+            (OpponentKind::Human, String::from("other_user"))
+        );
+        assert_eq!(
+            get_opponent(|player| player.user.as_mut().unwrap().title = Some("BOT".to_string())),
+            (OpponentKind::Bot, String::from("other_user"))
+        );
+        assert_eq!(
+            get_opponent(|player| {
+                player.user = None;
+                player.ai_level = Some(3);
+            }),
+            (OpponentKind::Computer, String::from("Stockfish level 3"))
+        );
+        assert_eq!(
+            get_opponent(|player| player.user = None),
+            (OpponentKind::Anonymous, String::from("Anonymous"))
+        );
+    }
+
+    #[test]
+    fn test_generate_timed_moves() {
+        // Lichess leaves the moves out of games where none was played.
+        let mut game = unit_test_util::get_some_mocked_game_a();
+        game.moves = None;
+        assert!(generate_timed_moves(&game).unwrap().is_empty());
+
+        // Extra clock stamps are ignored.
+        let game = unit_test_util::get_some_mocked_game_b();
+        let timed_moves = generate_timed_moves(&game).unwrap();
+        assert_eq!(timed_moves.len(), 6);
+        assert_eq!(timed_moves[5].move_key, "cxd4");
+        assert_eq!(timed_moves[5].move_time, 16000);
+    }
+
+    #[test]
+    fn test_get_excluded_game_warning() {
+        let game = unit_test_util::get_some_mocked_complete_game(1000);
+        let mut game_info = generate(&game, &0, "user").unwrap();
+        let mut request_data = ChessDataRequest::default();
+
+        let mut get_warnings = |game_info: &GameInfo| {
+            [
+                TournamentGamesFilter::Include,
+                TournamentGamesFilter::Only,
+                TournamentGamesFilter::Exclude,
+            ]
+            .map(|filter| {
+                request_data.tournament_games = Some(filter);
+                get_excluded_game_warning(game_info, &request_data)
+            })
+        };
+        assert_eq!(
+            get_warnings(&game_info),
+            [None, Some(GameFetchWarning::NotATournamentGame), None]
+        );
+
+        game_info.tournament_id = Some(String::from("arena1"));
+        assert_eq!(
+            get_warnings(&game_info),
+            [None, None, Some(GameFetchWarning::TournamentGame)]
+        );
+
+        // Bot accounts are kept by default, unlike the lichess AI.
+        game_info.tournament_id = None;
+        game_info.opponent_kind = OpponentKind::Bot;
+        assert_eq!(get_excluded_game_warning(&game_info, &request_data), None);
+        game_info.opponent_kind = OpponentKind::Computer;
+        assert_eq!(
+            get_excluded_game_warning(&game_info, &request_data),
+            Some(GameFetchWarning::ComputerOpponent)
+        );
+
+        game_info.opponent_kind = OpponentKind::Human;
+        game_info.rated = false;
+        assert_eq!(
+            get_excluded_game_warning(&game_info, &request_data),
+            Some(GameFetchWarning::CasualGame)
+        );
     }
 }
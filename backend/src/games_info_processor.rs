@@ -1,11 +1,70 @@
+use serde::Serialize;
 use std::collections::HashMap;
 
-use crate::games_info_generator::{GameInfo, TimedMove};
+use crate::games_info_generator::{GameInfo, OpponentKind, TimedMove};
+use crate::localization::{Locale, Message};
+use crate::premove_generator;
 use crate::service_intermediary::GameFetchWarning;
 use crate::util;
 
-const MIN_NUMBER_OF_PLIES_IN_GAME: usize = 30;  // At least 15 moves in the game to consider it for
-                                                // the analysis.
+// Each component of the consistency score is averaged over the games together with this many
+// virtual games at the typical value, so that a small sample cannot reach either end of the
+// scale. The typical values are estimates picked by hand, they were not measured on lichess
+// games and only set where a short history starts.
+const CONSISTENCY_PRIOR_GAMES_COUNT: f32 = 10.0;
+const TYPICAL_THINK_TIME_VARIATION: f32 = 1.0;
+const TYPICAL_TIME_TROUBLE_RATE: f32 = 0.3;
+const TYPICAL_HALF_TIME_DEFICIT_RATE: f32 = 0.5;
+const TYPICAL_FLAG_LOSS_RATE: f32 = 0.1;
+
+// Coefficient of variation of the think time, within a game, that scores 0. Picked by hand too.
+const MAX_THINK_TIME_VARIATION: f32 = 2.0;
+
+// Aggregated metrics over a subset of the analysed games (e.g a rating bracket).
+// Values are None when no game of the subset qualifies for them.
+#[derive(Serialize, Debug, PartialEq, Default)]
+pub struct GamesSummary {
+    pub games_count: usize,
+    pub average_time_differential: Option<f32>, // seconds
+    pub win_rate: Option<f32>,                  // Draws excluded, as in process_win_rate
+    pub user_flagged_opponent_rate: Option<f32>,
+    pub opponent_flagged_user_rate: Option<f32>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OpponentKindSummary {
+    pub opponent_kind: OpponentKind,
+    #[serde(flatten)]
+    pub summary: GamesSummary,
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyComponentKind {
+    ThinkTimeVariation,
+    TimeTrouble,
+    HalfTimeDeficit,
+    FlagLosses,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ConsistencyComponent {
+    pub kind: ConsistencyComponentKind,
+    pub value: f32,        // Average over the games
+    pub shrunk_value: f32, // Pulled towards the typical value, see CONSISTENCY_PRIOR_GAMES_COUNT
+    pub score: f32,        // From 0 to 100
+    pub weight: f32,
+    pub explanation: String,
+}
+
+// Composite score of how steadily the user manages the clock, 100 being the best.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ConsistencyScore {
+    pub score: f32, // Weighted average of the component scores
+    pub games_count: usize,
+    pub components: Vec<ConsistencyComponent>, // Only the components with data
+    pub previous_score: Option<f32>,           // Last one of the user, perf type and color
+}
 
 /// Heuristics:
 //  It really doesn't matter logistically if the half time differential is slightly offset.
@@ -18,7 +77,7 @@ fn get_half_moves(
     midpoint: usize,
     is_user_white: bool,
 ) -> (TimedMove, TimedMove) {
-    let (white_move_index, black_move_index) = if midpoint % 2 == 0 {
+    let (white_move_index, black_move_index) = if midpoint.is_multiple_of(2) {
         (midpoint, midpoint + 1)
     } else {
         (midpoint - 1, midpoint)
@@ -41,9 +100,15 @@ pub fn compute_curr_game_time_differential(game: &GameInfo) -> i32 {
     let is_user_white = game.user_color == "white";
 
     let (user_half_move, opponent_half_move) =
-        get_half_moves(&timed_moves, middle_cut_idx, is_user_white);
+        get_half_moves(timed_moves, middle_cut_idx, is_user_white);
 
-    (user_half_move.move_time - opponent_half_move.move_time) as i32
+    let (user_clock, opponent_clock) = util::get_berserk_adjusted_clocks(
+        game,
+        user_half_move.move_time,
+        opponent_half_move.move_time,
+    );
+
+    (user_clock - opponent_clock) as i32
 }
 
 pub fn get_half_time_differentials(
@@ -52,13 +117,21 @@ pub fn get_half_time_differentials(
     is_testing: bool,
 ) -> Vec<f32> {
     let mut half_time_differentials = Vec::new();
-    for (i, game_info) in games.iter().enumerate() {
+    for game_info in games.iter() {
+        let i = game_info.game_index;
         if skipped_games.contains_key(&i) {
             // The current game has already an internal error.
             // Skip it from the computation.
             continue;
         }
-        if !is_testing && game_info.timed_moves.len() < MIN_NUMBER_OF_PLIES_IN_GAME {
+        if game_info.timed_moves.is_empty() && game_info.plies_count > 0 {
+            // Moves were played without clocks, there is no half time differential to compute.
+            skipped_games
+                .entry(i)
+                .or_insert(GameFetchWarning::GameHasNoClocks);
+            continue;
+        }
+        if !is_testing && game_info.timed_moves.len() < game_info.variant.get_min_plies() {
             // Consider this block only in release.
             // skip this game and add it to vector of warnings with warning
             skipped_games
@@ -75,13 +148,13 @@ pub fn get_half_time_differentials(
     half_time_differentials
 }
 
-pub fn process_average_time(half_time_differentials: &Vec<f32>) -> Option<f32> {
-    if half_time_differentials.len() == 0 {
+pub fn process_average_time(half_time_differentials: &[f32]) -> Option<f32> {
+    if half_time_differentials.is_empty() {
         // NO games were kept in the computation. The time average is undefined
         return None;
     }
 
-    let average_half_time_differentials = util::compute_average(&half_time_differentials);
+    let average_half_time_differentials = util::compute_average(half_time_differentials);
     Some(average_half_time_differentials)
 }
 
@@ -92,8 +165,12 @@ pub fn process_win_rate(
     let mut n_games_considered = games.len();
     let mut n_wins = 0;
 
-    for (i, game_info) in games.iter().enumerate() {
-        if skipped_games.contains_key(&i) || util::is_game_draw(game_info) {
+    for game_info in games.iter() {
+        // Games without clocks still have a result.
+        let is_skipped = skipped_games
+            .get(&game_info.game_index)
+            .is_some_and(|warning| *warning != GameFetchWarning::GameHasNoClocks);
+        if is_skipped || util::is_game_draw(game_info) {
             // The current game has already an internal error.
             // Skip it from the computation.
             n_games_considered -= 1;
@@ -119,8 +196,8 @@ pub fn process_flag_info(
     let mut n_user_flags = 0;
     let mut n_opponent_flags = 0;
 
-    for (i, game_info) in games.iter().enumerate() {
-        if skipped_games.contains_key(&i) {
+    for game_info in games.iter() {
+        if util::is_game_skipped(game_info, skipped_games) {
             // The current game has already an internal error.
             // Skip it from the computation.
             continue;
@@ -142,9 +219,234 @@ pub fn process_flag_info(
     (n_user_flags, n_opponent_flags)
 }
 
+// Pairs every game kept in the computation with its half time differential. The differentials
+// are stored contiguously for the games that are not skipped.
+pub fn get_considered_games_with_differentials<'a>(
+    games: &'a [GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+    half_time_differentials: &[f32],
+) -> Vec<(&'a GameInfo, f32)> {
+    games
+        .iter()
+        .filter(|game_info| !util::is_game_skipped(game_info, skipped_games))
+        .zip(half_time_differentials.iter().copied())
+        .collect()
+}
+
+pub fn summarize_games(games_with_differentials: &[(&GameInfo, f32)]) -> GamesSummary {
+    let games_count = games_with_differentials.len();
+    if games_count == 0 {
+        return GamesSummary::default();
+    }
+
+    let time_differentials = games_with_differentials
+        .iter()
+        .map(|(_, time_differential)| *time_differential)
+        .collect::<Vec<f32>>();
+
+    let decisive_games = games_with_differentials
+        .iter()
+        .filter(|(game_info, _)| !util::is_game_draw(game_info))
+        .collect::<Vec<_>>();
+    let n_wins = decisive_games
+        .iter()
+        .filter(|(game_info, _)| util::has_user_won_game(game_info))
+        .count();
+
+    let (mut n_user_flags, mut n_opponent_flags) = (0, 0);
+    for (game_info, _) in games_with_differentials {
+        match util::get_game_flagging_information(game_info) {
+            Some(true) => n_user_flags += 1,
+            Some(false) => n_opponent_flags += 1,
+            None => {}
+        }
+    }
+
+    GamesSummary {
+        games_count,
+        average_time_differential: Some(util::compute_average(&time_differentials)),
+        win_rate: if decisive_games.is_empty() {
+            None
+        } else {
+            Some(n_wins as f32 / decisive_games.len() as f32)
+        },
+        user_flagged_opponent_rate: Some(n_user_flags as f32 / games_count as f32),
+        opponent_flagged_user_rate: Some(n_opponent_flags as f32 / games_count as f32),
+    }
+}
+
+// One summary per kind of opponent met in the games, humans first, e.g to compare the games
+// against bots with the others.
+pub fn summarize_games_per_opponent_kind(
+    games_with_differentials: &[(&GameInfo, f32)],
+) -> Vec<OpponentKindSummary> {
+    let mut opponent_kinds = games_with_differentials
+        .iter()
+        .map(|(game_info, _)| game_info.opponent_kind)
+        .collect::<Vec<OpponentKind>>();
+    opponent_kinds.sort();
+    opponent_kinds.dedup();
+
+    opponent_kinds
+        .into_iter()
+        .map(|opponent_kind| {
+            let games = games_with_differentials
+                .iter()
+                .filter(|(game_info, _)| game_info.opponent_kind == opponent_kind)
+                .copied()
+                .collect::<Vec<(&GameInfo, f32)>>();
+            OpponentKindSummary {
+                opponent_kind,
+                summary: summarize_games(&games),
+            }
+        })
+        .collect()
+}
+
+// Standard deviation of the think times of the user divided by their mean, within a game.
+fn get_think_time_variation(game_info: &GameInfo) -> Option<f32> {
+    let think_times = premove_generator::get_move_durations(game_info, true)
+        .iter()
+        .map(|move_duration| move_duration.duration as f32)
+        .collect::<Vec<f32>>();
+    if think_times.is_empty() {
+        return None;
+    }
+
+    let mean = util::compute_average(&think_times);
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = think_times
+        .iter()
+        .map(|think_time| (think_time - mean).powi(2))
+        .collect::<Vec<f32>>();
+    Some(util::compute_average(&variance).sqrt() / mean)
+}
+
+fn get_rate(values: impl Iterator<Item = bool>) -> Vec<f32> {
+    values.map(|value| if value { 1.0 } else { 0.0 }).collect()
+}
+
+// None without any game to measure the component on, rather than the typical value.
+fn get_consistency_component(
+    kind: ConsistencyComponentKind,
+    per_game_values: &[f32],
+    locale: Locale,
+) -> Option<ConsistencyComponent> {
+    if per_game_values.is_empty() {
+        return None;
+    }
+
+    let typical_value = match kind {
+        ConsistencyComponentKind::ThinkTimeVariation => TYPICAL_THINK_TIME_VARIATION,
+        ConsistencyComponentKind::TimeTrouble => TYPICAL_TIME_TROUBLE_RATE,
+        ConsistencyComponentKind::HalfTimeDeficit => TYPICAL_HALF_TIME_DEFICIT_RATE,
+        ConsistencyComponentKind::FlagLosses => TYPICAL_FLAG_LOSS_RATE,
+    };
+    let value = util::compute_average(per_game_values);
+    let shrunk_value = (per_game_values.iter().sum::<f32>()
+        + typical_value * CONSISTENCY_PRIOR_GAMES_COUNT)
+        / (per_game_values.len() as f32 + CONSISTENCY_PRIOR_GAMES_COUNT);
+
+    let (score, message) = match kind {
+        ConsistencyComponentKind::ThinkTimeVariation => (
+            1.0 - (shrunk_value / MAX_THINK_TIME_VARIATION).min(1.0),
+            Message::ConsistencyThinkTimeVariation { percentage: value },
+        ),
+        ConsistencyComponentKind::TimeTrouble => (
+            1.0 - shrunk_value,
+            Message::ConsistencyTimeTrouble { percentage: value },
+        ),
+        ConsistencyComponentKind::HalfTimeDeficit => (
+            1.0 - shrunk_value,
+            Message::ConsistencyHalfTimeDeficit { percentage: value },
+        ),
+        ConsistencyComponentKind::FlagLosses => (
+            1.0 - shrunk_value,
+            Message::ConsistencyFlagLosses { percentage: value },
+        ),
+    };
+
+    Some(ConsistencyComponent {
+        kind,
+        value,
+        shrunk_value,
+        score: score * 100.0,
+        weight: 1.0,
+        explanation: message.localize(locale),
+    })
+}
+
+// The components with data weigh the same, their effect on the results has not been measured.
+pub fn compute_consistency_score(
+    games_with_differentials: &[(&GameInfo, f32)],
+    locale: Locale,
+) -> Option<ConsistencyScore> {
+    if games_with_differentials.is_empty() {
+        return None;
+    }
+
+    let think_time_variations = games_with_differentials
+        .iter()
+        .filter_map(|(game_info, _)| get_think_time_variation(game_info))
+        .collect::<Vec<f32>>();
+    let time_trouble_rates = get_rate(games_with_differentials.iter().map(|(game_info, _)| {
+        premove_generator::has_reached_time_trouble(&premove_generator::get_move_durations(
+            game_info, true,
+        ))
+    }));
+    let half_time_deficit_rates = get_rate(
+        games_with_differentials
+            .iter()
+            .map(|(_, time_differential)| *time_differential < 0.0),
+    );
+    let flag_loss_rates = get_rate(
+        games_with_differentials
+            .iter()
+            .map(|(game_info, _)| util::get_game_flagging_information(game_info) == Some(false)),
+    );
+
+    let mut components = [
+        (
+            ConsistencyComponentKind::ThinkTimeVariation,
+            think_time_variations,
+        ),
+        (ConsistencyComponentKind::TimeTrouble, time_trouble_rates),
+        (
+            ConsistencyComponentKind::HalfTimeDeficit,
+            half_time_deficit_rates,
+        ),
+        (ConsistencyComponentKind::FlagLosses, flag_loss_rates),
+    ]
+    .into_iter()
+    .filter_map(|(kind, per_game_values)| get_consistency_component(kind, &per_game_values, locale))
+    .collect::<Vec<ConsistencyComponent>>();
+    if components.is_empty() {
+        return None;
+    }
+    let weight = 1.0 / components.len() as f32;
+    for component in components.iter_mut() {
+        component.weight = weight;
+    }
+
+    Some(ConsistencyScore {
+        score: components
+            .iter()
+            .map(|component| component.score * component.weight)
+            .sum(),
+        games_count: games_with_differentials.len(),
+        components,
+        previous_score: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::unit_test_util::MinimalGameJsonInfoTesting;
     use crate::util::convert_centiseconds_to_seconds;
+    use crate::variant::Variant;
+    use crate::{games_info_generator, unit_test_util};
 
     use super::*;
 
@@ -225,16 +527,16 @@ mod tests {
             let game_a = unit_test_util::get_some_mocked_game_a();
             let game_b = unit_test_util::get_some_mocked_game_b();
             let input_games = vec![
-                games_info_generator::generate(&game_a, &0, &"user".to_string()),
-                games_info_generator::generate(&game_b, &1, &"user".to_string()),
+                games_info_generator::generate(&game_a, &0, "user").unwrap(),
+                games_info_generator::generate(&game_b, &1, "user").unwrap(),
             ];
             let half_time_differentials =
                 get_half_time_differentials(&input_games, &mut HashMap::new(), true);
             let res = process_average_time(&half_time_differentials);
-            assert_eq!(res.is_some(), true);
+            assert!(res.is_some());
 
-            let expected_average = -(5.78 + 0.08) / 2.0;
-            assert_eq!(res.unwrap(), expected_average as f32);
+            let expected_average = (-0.08 + 5.78) / 2.0;
+            assert_eq!(res.unwrap(), expected_average);
         }
 
         // Average for 0 games
@@ -244,7 +546,7 @@ mod tests {
                 get_half_time_differentials(&input_games, &mut HashMap::new(), true);
             let res = process_average_time(&half_time_differentials);
 
-            assert_eq!(res.is_none(), true);
+            assert!(res.is_none());
         }
 
         // 2 game and second game skipped
@@ -252,8 +554,8 @@ mod tests {
             let game_a = unit_test_util::get_some_mocked_game_a();
             let game_b = unit_test_util::get_some_mocked_game_b();
             let input_games = vec![
-                games_info_generator::generate(&game_a, &0, &"user".to_string()),
-                games_info_generator::generate(&game_b, &1, &"user".to_string()),
+                games_info_generator::generate(&game_a, &0, "user").unwrap(),
+                games_info_generator::generate(&game_b, &1, "user").unwrap(),
             ];
 
             let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
@@ -265,8 +567,164 @@ mod tests {
                 get_half_time_differentials(&input_games, &mut skipped_games, true);
             let res = process_average_time(&half_time_differentials);
 
-            assert_eq!(res.is_some(), true);
+            assert!(res.is_some());
             assert_eq!(res.unwrap(), convert_centiseconds_to_seconds(-8));
         }
     }
+
+    #[test]
+    fn test_get_half_time_differentials_min_plies_per_variant() {
+        // 24 plies with crazyhouse drops: long enough for crazyhouse but not for standard chess.
+        let plies_count = 24;
+        let mut game = unit_test_util::create_mock_game_json(MinimalGameJsonInfoTesting {
+            clocks: Some((0..plies_count).map(|ply| 18000 - ply * 100).collect()),
+            moves: Some(vec!["e4 d5 exd5 Qxd5 P@e4 Qa5"; plies_count as usize / 6].join(" ")),
+            black_player_name: Some("user".to_string()),
+            white_player_name: Some("other_user".to_string()),
+            winner: Some("white".to_string()),
+        });
+        let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
+
+        let standard_game = games_info_generator::generate(&game, &0, "user").unwrap();
+        assert_eq!(standard_game.timed_moves[4].move_key, "P@e4");
+        let half_time_differentials =
+            get_half_time_differentials(&[standard_game], &mut skipped_games, false);
+        assert!(half_time_differentials.is_empty());
+        assert_eq!(
+            skipped_games.get(&0),
+            Some(&GameFetchWarning::GameHasNotEnoughMoves)
+        );
+
+        game.variant = Some("crazyhouse".to_string());
+        skipped_games.clear();
+        let crazyhouse_game = games_info_generator::generate(&game, &0, "user").unwrap();
+        assert_eq!(crazyhouse_game.variant, Variant::Crazyhouse);
+        assert_eq!(
+            get_half_time_differentials(&[crazyhouse_game], &mut skipped_games, false),
+            [-1.0]
+        );
+        assert!(skipped_games.is_empty());
+    }
+
+    #[test]
+    fn test_get_half_time_differentials_berserk() {
+        let game = unit_test_util::get_some_mocked_complete_game(1000);
+        let expected_differentials = get_half_time_differentials(
+            &[games_info_generator::generate(&game, &0, "user").unwrap()],
+            &mut HashMap::new(),
+            true,
+        );
+
+        // The user plays black and berserks: 90 of the 180 seconds are taken off each clock.
+        let mut berserked_game = unit_test_util::get_some_mocked_complete_game(1000);
+        berserked_game.tournament = Some("arena1".to_string());
+        let players = berserked_game.players.as_mut().unwrap();
+        players.black.as_mut().unwrap().berserk = Some(true);
+        for clock in berserked_game
+            .clocks
+            .as_mut()
+            .unwrap()
+            .iter_mut()
+            .skip(1)
+            .step_by(2)
+        {
+            *clock -= 9000;
+        }
+        let berserked_game_info =
+            games_info_generator::generate(&berserked_game, &0, "user").unwrap();
+        assert!(berserked_game_info.user_berserked);
+        assert!(!berserked_game_info.opponent_berserked);
+        assert_eq!(berserked_game_info.berserk_time, 9000);
+        assert_eq!(
+            get_half_time_differentials(&[berserked_game_info], &mut HashMap::new(), true),
+            expected_differentials
+        );
+    }
+
+    #[test]
+    fn test_compute_consistency_score() {
+        // The user plays black at a steady 2 seconds per move and is always behind at half
+        // time, without time trouble nor flag losses.
+        let game_info = games_info_generator::generate(
+            &unit_test_util::get_some_mocked_complete_game(1000),
+            &0,
+            "user",
+        )
+        .unwrap();
+        let get_consistency_score = |games_count: usize| {
+            let games_with_differentials = vec![(&game_info, -20.0); games_count];
+            compute_consistency_score(&games_with_differentials, Locale::En).unwrap()
+        };
+        let get_component_scores = |consistency_score: &ConsistencyScore| {
+            consistency_score
+                .components
+                .iter()
+                .map(|component| (component.kind, component.value, component.score))
+                .collect::<Vec<(ConsistencyComponentKind, f32, f32)>>()
+        };
+        let assert_close = |actual: f32, expected: f32| assert!((actual - expected).abs() < 1e-3);
+
+        // 3 games are pulled towards the typical values by the 10 virtual games.
+        let small_sample = get_consistency_score(3);
+        assert_eq!(small_sample.games_count, 3);
+        let component_scores = get_component_scores(&small_sample);
+        assert_eq!(
+            component_scores
+                .iter()
+                .map(|(kind, value, _)| (*kind, *value))
+                .collect::<Vec<(ConsistencyComponentKind, f32)>>(),
+            [
+                (ConsistencyComponentKind::ThinkTimeVariation, 0.0),
+                (ConsistencyComponentKind::TimeTrouble, 0.0),
+                (ConsistencyComponentKind::HalfTimeDeficit, 1.0),
+                (ConsistencyComponentKind::FlagLosses, 0.0),
+            ]
+        );
+        assert_close(component_scores[0].2, 100.0 * (1.0 - 10.0 / 13.0 / 2.0));
+        assert_close(component_scores[1].2, 100.0 * (1.0 - 3.0 / 13.0));
+        assert_close(component_scores[2].2, 100.0 * (1.0 - 8.0 / 13.0));
+        assert_close(component_scores[3].2, 100.0 * (1.0 - 1.0 / 13.0));
+        assert_close(small_sample.score, 67.3077);
+        assert_eq!(
+            small_sample.components[2].explanation,
+            "100% of the games were behind on the clock at half time."
+        );
+
+        // The same games in a larger sample move each component further from the typical value.
+        let large_sample = get_consistency_score(30);
+        let component_scores = get_component_scores(&large_sample);
+        assert_close(component_scores[0].2, 87.5);
+        assert_close(component_scores[2].2, 12.5);
+        assert_close(large_sample.score, 72.5);
+
+        // Without think times, the think time variation is left out of the score.
+        let mut game_info_without_moves = games_info_generator::generate(
+            &unit_test_util::get_some_mocked_complete_game(1000),
+            &0,
+            "user",
+        )
+        .unwrap();
+        game_info_without_moves.timed_moves.clear();
+        let games_with_differentials = vec![(&game_info_without_moves, -20.0); 3];
+        let without_moves =
+            compute_consistency_score(&games_with_differentials, Locale::En).unwrap();
+        assert_eq!(
+            without_moves
+                .components
+                .iter()
+                .map(|component| (component.kind, component.weight))
+                .collect::<Vec<(ConsistencyComponentKind, f32)>>(),
+            [
+                (ConsistencyComponentKind::TimeTrouble, 1.0 / 3.0),
+                (ConsistencyComponentKind::HalfTimeDeficit, 1.0 / 3.0),
+                (ConsistencyComponentKind::FlagLosses, 1.0 / 3.0),
+            ]
+        );
+        assert_close(
+            without_moves.score,
+            100.0 * (1.0 - (3.0 / 13.0 + 8.0 / 13.0 + 1.0 / 13.0) / 3.0),
+        );
+
+        assert_eq!(compute_consistency_score(&[], Locale::En), None);
+    }
 }
@@ -1,97 +1,699 @@
-use crate::service_intermediary::{ChessDataRequest, DescriptionMessageAssessment};
+use crate::clock_curve_generator::ClockCurvePoint;
+use crate::correspondence_generator::CorrespondenceAnalysis;
+use crate::games_info_processor::GamesSummary;
+use crate::localization::{Locale, Message};
+use crate::premove_generator::PremoveAnalysis;
+use crate::rating_bucket_generator::OpponentRatingBucket;
+use crate::service_intermediary::DescriptionMessageAssessment;
+use crate::session_generator::SessionsAnalysis;
+use crate::time_of_day_generator::TimeOfDayAnalysis;
+use crate::trend_chart_generator::TrendLine;
+use crate::util::MIN_GAMES_FOR_COMPARISON;
+use crate::variant::Variant;
 
-const INVALID_TIME_DESCRIPTION_PLACEHOLDER_MSG: &str =
-    "The time value was not computed. Check the errors panel for more information.";
+use serde::Serialize;
+
+// The user is considered to slow down against higher rated opponents when their half time
+// differential drops by at least this many seconds compared to the other games.
+const SIGNIFICANT_RATING_SLOWDOWN_IN_SECONDS: f32 = 5.0;
+const SIGNIFICANT_HALF_TIME_DEFICIT_IN_SECONDS: f32 = 10.0;
+
+const SIGNIFICANT_OPENING_DEFICIT_IN_SECONDS: f32 = 10.0;
+
+// Share of the games lost on time.
+const FREQUENT_FLAGGING_RATE: f32 = 0.1;
+const CRITICAL_FLAGGING_RATE: f32 = 0.25;
+
+// An hour of the day stands out when the user loses on time there at least this many times
+// more often than overall.
+const FLAGGING_HOUR_RATIO: f32 = 2.0;
+
+// Share of the timed moves of the user played instantly.
+const FREQUENT_INSTANT_MOVE_RATE: f32 = 0.2;
+
+// Total drift of the half time differential over the sample given by the trend line.
+const SIGNIFICANT_TREND_DRIFT_IN_SECONDS: f32 = 5.0;
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InsightCategory {
+    TimeManagement,
+    TimeTrouble,
+    GamePhases,
+    Opponents,
+    Sessions,
+    Schedule,
+    Progress,
+}
+
+// Ordered from the least to the most important.
+#[derive(Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum InsightSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Insight {
+    pub category: InsightCategory,
+    pub severity: InsightSeverity,
+    pub assessment: DescriptionMessageAssessment,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+// Every metric the rules can inspect.
+pub struct InsightMetrics<'a> {
+    pub username: &'a str,
+    pub locale: Locale,
+    pub variant: Variant,
+    pub average_time: Option<f32>,
+    pub overall_summary: &'a GamesSummary,
+    pub average_clock_curve: &'a [ClockCurvePoint],
+    pub opponent_rating_buckets: &'a [OpponentRatingBucket],
+    pub sessions: &'a SessionsAnalysis,
+    pub time_of_day: &'a TimeOfDayAnalysis,
+    pub trend_line: Option<&'a TrendLine>,
+    pub correspondence: Option<&'a CorrespondenceAnalysis>,
+    pub premoves: &'a PremoveAnalysis,
+}
+
+pub type InsightRule = fn(&InsightMetrics) -> Option<Insight>;
+
+// Rules are listed by priority: among insights of the same severity, the first rules rank first.
+const INSIGHT_RULES: &[InsightRule] = &[
+    get_average_time_insight,
+    get_flagging_insight,
+    get_opening_time_insight,
+    get_opponent_rating_insight,
+    get_speed_up_after_losses_insight,
+    get_results_after_losses_insight,
+    get_long_sessions_insight,
+    get_flagging_hour_insight,
+    get_trend_insight,
+    get_correspondence_insight,
+    get_premove_insight,
+];
 
 // Everything is a string for proper serialization to frontend
 pub struct InsightsPanelProps {
     pub average_time: String,
     pub explanation_message: (String, DescriptionMessageAssessment),
     pub win_ratio: String,
+    pub insights: Vec<Insight>,
 }
 
-pub struct MessageContext {
-    average_time: f32,
+fn get_average_time_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let Some(average_time) = metrics.average_time else {
+        // Correspondence games have no clocks, their time usage has its own insight.
+        if metrics.correspondence.is_some() {
+            return None;
+        }
+        return Some(Insight {
+            category: InsightCategory::TimeManagement,
+            severity: InsightSeverity::Critical,
+            assessment: DescriptionMessageAssessment::Negative,
+            message: Message::TimeNotComputed.localize(metrics.locale),
+            suggestion: None,
+        });
+    };
+
+    let username = metrics.username.to_string();
+    let (message, assessment) = match average_time.partial_cmp(&0.0) {
+        Some(std::cmp::Ordering::Less) => (
+            Message::AverageTimeBehind {
+                username,
+                seconds: average_time.abs(),
+            },
+            DescriptionMessageAssessment::Negative,
+        ),
+        Some(std::cmp::Ordering::Greater) => (
+            Message::AverageTimeAhead {
+                username,
+                seconds: average_time,
+            },
+            DescriptionMessageAssessment::Positive,
+        ),
+        _ => (
+            Message::AverageTimeEqual { username },
+            DescriptionMessageAssessment::Neutral,
+        ),
+    };
+
+    let is_significant_deficit = average_time <= -SIGNIFICANT_HALF_TIME_DEFICIT_IN_SECONDS;
+    Some(Insight {
+        category: InsightCategory::TimeManagement,
+        severity: if is_significant_deficit {
+            InsightSeverity::Warning
+        } else {
+            InsightSeverity::Info
+        },
+        assessment,
+        message: message.localize(metrics.locale),
+        suggestion: is_significant_deficit
+            .then(|| Message::SteadyPaceSuggestion.localize(metrics.locale)),
+    })
 }
 
-impl MessageContext {
-    pub fn new(average_time_opt: Option<f32>) -> Self {
-        Self {
-            average_time: average_time_opt.unwrap_or(std::f32::MAX),
+fn get_flagging_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let summary = metrics.overall_summary;
+    let flagged_rate = summary.opponent_flagged_user_rate?;
+    if summary.games_count < MIN_GAMES_FOR_COMPARISON || flagged_rate < FREQUENT_FLAGGING_RATE {
+        return None;
+    }
+
+    Some(Insight {
+        category: InsightCategory::TimeTrouble,
+        severity: if flagged_rate >= CRITICAL_FLAGGING_RATE {
+            InsightSeverity::Critical
+        } else {
+            InsightSeverity::Warning
+        },
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::GamesLostOnTime {
+            username: metrics.username.to_string(),
+            percentage: flagged_rate,
         }
+        .localize(metrics.locale),
+        suggestion: Some(Message::TimeTroubleSuggestion.localize(metrics.locale)),
+    })
+}
+
+fn get_opening_time_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let opening_moves_count = metrics.variant.get_opening_moves_count()?;
+    let opening_point = metrics.average_clock_curve.get(opening_moves_count - 1)?;
+    if opening_point.games_count < MIN_GAMES_FOR_COMPARISON {
+        return None;
+    }
+
+    let opening_deficit = opening_point.opponent_clock - opening_point.user_clock;
+    if opening_deficit < SIGNIFICANT_OPENING_DEFICIT_IN_SECONDS {
+        return None;
     }
 
-    pub fn generate_message(
-        &self,
-        request_data: &ChessDataRequest,
-    ) -> (String, DescriptionMessageAssessment) {
-        if self.average_time == std::f32::MAX {
-            return (
-                INVALID_TIME_DESCRIPTION_PLACEHOLDER_MSG.to_string(),
-                DescriptionMessageAssessment::Negative,
-            );
+    Some(Insight {
+        category: InsightCategory::GamePhases,
+        severity: InsightSeverity::Warning,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::OpeningTimeDeficit {
+            username: metrics.username.to_string(),
+            moves_count: opening_moves_count,
+            seconds: opening_deficit,
         }
+        .localize(metrics.locale),
+        suggestion: Some(Message::OpeningPreparationSuggestion.localize(metrics.locale)),
+    })
+}
 
-        let (status_message, assessment) = match self.average_time.partial_cmp(&0.0).unwrap() {
-            std::cmp::Ordering::Less => (
-                "behind their opponents by",
-                DescriptionMessageAssessment::Negative,
-            ),
-            std::cmp::Ordering::Equal => (
-                "equal in time to their opponents",
-                DescriptionMessageAssessment::Neutral,
-            ),
-            std::cmp::Ordering::Greater => (
-                "ahead of their opponents by",
-                DescriptionMessageAssessment::Positive,
+fn get_correspondence_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let correspondence = metrics.correspondence?;
+
+    let timeout_rate = correspondence.user_timeout_count as f32 / correspondence.games_count as f32;
+    if timeout_rate >= FREQUENT_FLAGGING_RATE {
+        return Some(Insight {
+            category: InsightCategory::TimeTrouble,
+            severity: InsightSeverity::Warning,
+            assessment: DescriptionMessageAssessment::Negative,
+            message: Message::CorrespondenceTimeouts {
+                username: metrics.username.to_string(),
+                percentage: timeout_rate,
+            }
+            .localize(metrics.locale),
+            suggestion: Some(Message::CorrespondenceTimeoutSuggestion.localize(metrics.locale)),
+        });
+    }
+
+    Some(Insight {
+        category: InsightCategory::TimeManagement,
+        severity: InsightSeverity::Info,
+        assessment: DescriptionMessageAssessment::Neutral,
+        message: Message::CorrespondenceTimeUsage {
+            username: metrics.username.to_string(),
+            hours: correspondence.average_hours_per_move?,
+            percentage: correspondence.allowance_used_rate?,
+        }
+        .localize(metrics.locale),
+        suggestion: None,
+    })
+}
+
+// Games-weighted average of the half time differential over the given buckets.
+fn get_weighted_average_time<'a>(
+    buckets: impl Iterator<Item = &'a OpponentRatingBucket>,
+) -> Option<(f32, usize)> {
+    let (time_sum, games_count) = buckets.fold((0.0, 0), |(time_sum, games_count), bucket| {
+        match bucket.summary.average_time_differential {
+            Some(average_time) => (
+                time_sum + average_time * bucket.summary.games_count as f32,
+                games_count + bucket.summary.games_count,
             ),
-        };
+            None => (time_sum, games_count),
+        }
+    });
 
-        let time_message = if self.average_time != std::f32::MAX {
-            format!(" {:.2} seconds", self.average_time.abs())
-        } else {
-            String::new()
-        };
+    if games_count == 0 {
+        return None;
+    }
+    Some((time_sum / games_count as f32, games_count))
+}
 
-        let message = format!(
-            "On average, {} is {}{} at half time in the games.",
-            request_data.username, status_message, time_message
-        );
+fn get_opponent_rating_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let (higher_rated_average_time, higher_rated_games_count) = get_weighted_average_time(
+        metrics
+            .opponent_rating_buckets
+            .iter()
+            .filter(|bucket| bucket.is_higher_rated()),
+    )?;
+    let (other_average_time, other_games_count) = get_weighted_average_time(
+        metrics
+            .opponent_rating_buckets
+            .iter()
+            .filter(|bucket| !bucket.is_higher_rated()),
+    )?;
+
+    if higher_rated_games_count < MIN_GAMES_FOR_COMPARISON
+        || other_games_count < MIN_GAMES_FOR_COMPARISON
+    {
+        return None;
+    }
 
-        (message, assessment)
+    let slowdown = other_average_time - higher_rated_average_time;
+    if slowdown < SIGNIFICANT_RATING_SLOWDOWN_IN_SECONDS {
+        return None;
     }
+
+    Some(Insight {
+        category: InsightCategory::Opponents,
+        severity: InsightSeverity::Warning,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::HigherRatedOpponentsSlowdown {
+            username: metrics.username.to_string(),
+            seconds: slowdown,
+        }
+        .localize(metrics.locale),
+        suggestion: Some(Message::HigherRatedOpponentsSuggestion.localize(metrics.locale)),
+    })
 }
 
-pub fn get_average_time_as_formatted_string(
-    average_half_time_differential_opt: Option<f32>,
-) -> String {
-    if average_half_time_differential_opt.is_none() {
-        return String::from("Undefined ");
+fn get_speed_up_after_losses_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    if !metrics.sessions.tilt.speeds_up_after_losses {
+        return None;
+    }
+
+    Some(Insight {
+        category: InsightCategory::Sessions,
+        severity: InsightSeverity::Warning,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::SpeedUpAfterLosses {
+            username: metrics.username.to_string(),
+        }
+        .localize(metrics.locale),
+        suggestion: Some(Message::BreakAfterLossesSuggestion.localize(metrics.locale)),
+    })
+}
+
+fn get_results_after_losses_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    if !metrics.sessions.tilt.results_degrade_after_losses {
+        return None;
+    }
+
+    Some(Insight {
+        category: InsightCategory::Sessions,
+        severity: InsightSeverity::Warning,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::ResultsDegradeAfterLosses {
+            username: metrics.username.to_string(),
+        }
+        .localize(metrics.locale),
+        suggestion: Some(Message::EndSessionAfterLossesSuggestion.localize(metrics.locale)),
+    })
+}
+
+fn get_long_sessions_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    if !metrics.sessions.tilt.results_degrade_in_long_sessions {
+        return None;
+    }
+
+    Some(Insight {
+        category: InsightCategory::Sessions,
+        severity: InsightSeverity::Info,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::ResultsDegradeInLongSessions {
+            username: metrics.username.to_string(),
+        }
+        .localize(metrics.locale),
+        suggestion: Some(Message::ShorterSessionsSuggestion.localize(metrics.locale)),
+    })
+}
+
+fn get_flagging_hour_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let overall_flagged_rate = metrics.overall_summary.opponent_flagged_user_rate?;
+    let worst_hour = metrics
+        .time_of_day
+        .hours_of_day
+        .iter()
+        .filter(|bucket| bucket.summary.games_count >= MIN_GAMES_FOR_COMPARISON)
+        .filter_map(|bucket| {
+            bucket
+                .summary
+                .opponent_flagged_user_rate
+                .map(|flagged_rate| (bucket.hour, flagged_rate))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    let (hour, flagged_rate) = worst_hour;
+    if flagged_rate < FREQUENT_FLAGGING_RATE
+        || flagged_rate < overall_flagged_rate * FLAGGING_HOUR_RATIO
+    {
+        return None;
+    }
+
+    Some(Insight {
+        category: InsightCategory::Schedule,
+        severity: InsightSeverity::Warning,
+        assessment: DescriptionMessageAssessment::Negative,
+        message: Message::GamesLostOnTimeAtHour {
+            username: metrics.username.to_string(),
+            hour,
+            timezone: metrics.time_of_day.timezone.clone(),
+            percentage: flagged_rate,
+        }
+        .localize(metrics.locale),
+        suggestion: Some(Message::AvoidHourSuggestion.localize(metrics.locale)),
+    })
+}
+
+fn get_trend_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let trend_line = metrics.trend_line?;
+    let drift = trend_line.slope * metrics.overall_summary.games_count as f32;
+    if drift.abs() < SIGNIFICANT_TREND_DRIFT_IN_SECONDS {
+        return None;
+    }
+
+    let username = metrics.username.to_string();
+    let seconds = drift.abs();
+    let (message, assessment) = if drift > 0.0 {
+        (
+            Message::TrendImproved { username, seconds },
+            DescriptionMessageAssessment::Positive,
+        )
     } else {
-        return average_half_time_differential_opt.unwrap().to_string();
+        (
+            Message::TrendWorsened { username, seconds },
+            DescriptionMessageAssessment::Negative,
+        )
+    };
+    Some(Insight {
+        category: InsightCategory::Progress,
+        severity: InsightSeverity::Info,
+        assessment,
+        message: message.localize(metrics.locale),
+        suggestion: None,
+    })
+}
+
+fn get_premove_insight(metrics: &InsightMetrics) -> Option<Insight> {
+    let premoves = metrics.premoves;
+    let instant_move_rate = premoves.premove_reliance.rate?;
+    if metrics.overall_summary.games_count < MIN_GAMES_FOR_COMPARISON
+        || instant_move_rate < FREQUENT_INSTANT_MOVE_RATE
+    {
+        return None;
     }
+
+    let username = metrics.username.to_string();
+    if premoves.results_degrade_with_instant_moves {
+        return Some(Insight {
+            category: InsightCategory::TimeManagement,
+            severity: InsightSeverity::Warning,
+            assessment: DescriptionMessageAssessment::Negative,
+            message: Message::InstantMovesCostGames {
+                username,
+                win_rate: premoves.frequent_instant_moves_games.win_rate?,
+                other_win_rate: premoves.other_games.win_rate?,
+            }
+            .localize(metrics.locale),
+            suggestion: Some(Message::InstantMovesSuggestion.localize(metrics.locale)),
+        });
+    }
+
+    Some(Insight {
+        category: InsightCategory::TimeManagement,
+        severity: InsightSeverity::Info,
+        assessment: DescriptionMessageAssessment::Neutral,
+        message: Message::InstantMoves {
+            username,
+            percentage: instant_move_rate,
+        }
+        .localize(metrics.locale),
+        suggestion: None,
+    })
 }
 
-pub fn get_feedback_message(
+// Runs every rule and ranks the resulting insights by decreasing severity, then by rule
+// priority.
+pub fn generate_insights(metrics: &InsightMetrics) -> Vec<Insight> {
+    let mut insights = INSIGHT_RULES
+        .iter()
+        .filter_map(|rule| rule(metrics))
+        .collect::<Vec<Insight>>();
+    insights.sort_by_key(|insight| std::cmp::Reverse(insight.severity));
+    insights
+}
+
+pub fn get_average_time_as_formatted_string(
     average_half_time_differential_opt: Option<f32>,
-    request_data: &ChessDataRequest,
-) -> (String, DescriptionMessageAssessment) {
-    let context = MessageContext::new(average_half_time_differential_opt);
-    context.generate_message(request_data)
+) -> String {
+    match average_half_time_differential_opt {
+        Some(average_half_time_differential) => average_half_time_differential.to_string(),
+        None => String::from("Undefined "),
+    }
 }
 
 pub fn get_win_ratio_as_formatted_string(player_win_rate_in_fetched_games: f32) -> String {
     format!("{:.2}", player_win_rate_in_fetched_games)
 }
 
+// The first insight is the headline. No rule fires when no move could be timed, e.g for
+// correspondence games without any recorded move time, the headline then points to the errors
+// panel.
 pub fn get_insights(
-    average_half_time_differential_opt: Option<f32>,
     player_win_rate_in_fetched_games: f32,
-    request_data: &ChessDataRequest,
+    metrics: &InsightMetrics,
 ) -> InsightsPanelProps {
+    let insights = generate_insights(metrics);
+    let explanation_message = match insights.first() {
+        Some(insight) => (insight.message.clone(), insight.assessment),
+        None => (
+            Message::TimeNotComputed.localize(metrics.locale),
+            DescriptionMessageAssessment::Negative,
+        ),
+    };
+
     InsightsPanelProps {
-        average_time: get_average_time_as_formatted_string(average_half_time_differential_opt),
-        explanation_message: get_feedback_message(average_half_time_differential_opt, request_data),
+        average_time: get_average_time_as_formatted_string(metrics.average_time),
+        explanation_message,
         win_ratio: get_win_ratio_as_formatted_string(player_win_rate_in_fetched_games),
+        insights,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{premove_generator, session_generator, time_of_day_generator};
+
+    // Owns the analyses the metrics borrow, all of them empty.
+    struct MetricsData {
+        overall_summary: GamesSummary,
+        sessions: SessionsAnalysis,
+        time_of_day: TimeOfDayAnalysis,
+        premoves: PremoveAnalysis,
+    }
+
+    impl MetricsData {
+        fn new() -> Self {
+            Self {
+                overall_summary: GamesSummary::default(),
+                sessions: session_generator::generate(&[], 30),
+                time_of_day: time_of_day_generator::generate(&[], chrono_tz::Tz::UTC),
+                premoves: premove_generator::generate(&[]),
+            }
+        }
+
+        fn metrics(&self) -> InsightMetrics<'_> {
+            InsightMetrics {
+                username: "user",
+                locale: Locale::En,
+                variant: Variant::Standard,
+                average_time: None,
+                overall_summary: &self.overall_summary,
+                average_clock_curve: &[],
+                opponent_rating_buckets: &[],
+                sessions: &self.sessions,
+                time_of_day: &self.time_of_day,
+                trend_line: None,
+                correspondence: None,
+                premoves: &self.premoves,
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_insights() {
+        let data = MetricsData {
+            overall_summary: GamesSummary {
+                games_count: 10,
+                average_time_differential: Some(-12.0),
+                win_rate: Some(0.4),
+                user_flagged_opponent_rate: Some(0.0),
+                opponent_flagged_user_rate: Some(0.3),
+            },
+            ..MetricsData::new()
+        };
+        let trend_line = TrendLine {
+            slope: 1.0,
+            intercept: -15.0,
+        };
+        let metrics = InsightMetrics {
+            average_time: Some(-12.0),
+            trend_line: Some(&trend_line),
+            ..data.metrics()
+        };
+
+        let insights = generate_insights(&metrics);
+        let ranking = insights
+            .iter()
+            .map(|insight| (insight.category, insight.severity))
+            .collect::<Vec<(InsightCategory, InsightSeverity)>>();
+        assert_eq!(
+            ranking,
+            [
+                (InsightCategory::TimeTrouble, InsightSeverity::Critical),
+                (InsightCategory::TimeManagement, InsightSeverity::Warning),
+                (InsightCategory::Progress, InsightSeverity::Info),
+            ]
+        );
+        assert_eq!(
+            insights[1].message,
+            "On average, user is behind their opponents by 12.00 seconds at half time in the games."
+        );
+        assert_eq!(
+            insights[2].assessment,
+            DescriptionMessageAssessment::Positive
+        );
+
+        let panel = get_insights(0.4, &metrics);
+        assert_eq!(panel.explanation_message.0, insights[0].message);
+    }
+
+    #[test]
+    fn test_get_insights_without_any_insight() {
+        // Correspondence games whose moves could not be timed.
+        let correspondence = CorrespondenceAnalysis {
+            games_count: 2,
+            average_days_per_turn: 3.0,
+            average_hours_per_move: None,
+            allowance_used_rate: None,
+            user_timeout_count: 0,
+            opponent_timeout_count: 0,
+        };
+        let data = MetricsData::new();
+        let metrics = InsightMetrics {
+            correspondence: Some(&correspondence),
+            ..data.metrics()
+        };
+
+        let panel = get_insights(0.5, &metrics);
+        assert!(panel.insights.is_empty());
+        assert_eq!(
+            panel.explanation_message,
+            (
+                Message::TimeNotComputed.localize(Locale::En),
+                DescriptionMessageAssessment::Negative
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_opening_time_insight() {
+        // The user is 15 seconds behind from the 6th move on.
+        let average_clock_curve = (1..=12)
+            .map(|move_number| ClockCurvePoint {
+                move_number,
+                user_clock: if move_number < 6 { 180.0 } else { 165.0 },
+                opponent_clock: 180.0,
+                games_count: 5,
+            })
+            .collect::<Vec<ClockCurvePoint>>();
+        let data = MetricsData::new();
+        let get_insight_message = |variant: Variant| {
+            get_opening_time_insight(&InsightMetrics {
+                variant,
+                average_time: Some(-15.0),
+                average_clock_curve: &average_clock_curve,
+                ..data.metrics()
+            })
+            .map(|insight| insight.message)
+        };
+
+        assert_eq!(
+            get_insight_message(Variant::Standard).as_deref(),
+            Some("After 10 moves, user is already 15.00 seconds behind their opponents.")
+        );
+        assert_eq!(
+            get_insight_message(Variant::Atomic).as_deref(),
+            Some("After 6 moves, user is already 15.00 seconds behind their opponents.")
+        );
+        // Chess960 has no opening preparation, spending time early is expected.
+        assert_eq!(get_insight_message(Variant::Chess960), None);
+    }
+
+    #[test]
+    fn test_get_premove_insight() {
+        let data = MetricsData {
+            overall_summary: GamesSummary {
+                games_count: 6,
+                ..Default::default()
+            },
+            ..MetricsData::new()
+        };
+        let get_insight = |premoves: &PremoveAnalysis| {
+            get_premove_insight(&InsightMetrics {
+                average_time: Some(0.0),
+                premoves,
+                ..data.metrics()
+            })
+            .map(|insight| (insight.severity, insight.message))
+        };
+
+        let mut premoves = PremoveAnalysis::default();
+        premoves.premove_reliance.rate = Some(0.1);
+        assert_eq!(get_insight(&premoves), None);
+
+        premoves.premove_reliance.rate = Some(0.25);
+        assert_eq!(
+            get_insight(&premoves),
+            Some((
+                InsightSeverity::Info,
+                String::from("user plays 25% of their moves instantly, premoves included.")
+            ))
+        );
+
+        premoves.results_degrade_with_instant_moves = true;
+        premoves.frequent_instant_moves_games.win_rate = Some(0.3);
+        premoves.other_games.win_rate = Some(0.6);
+        assert_eq!(
+            get_insight(&premoves),
+            Some((
+                InsightSeverity::Warning,
+                String::from(
+                    "user wins 30% of the games in which they move instantly the most, against \
+                     60% of the other games."
+                )
+            ))
+        );
     }
 }
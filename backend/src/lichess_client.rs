@@ -1,44 +1,126 @@
 use actix::Addr;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::deserialization::GameJson;
+use crate::clock_curve_generator;
+use crate::correspondence_generator;
+use crate::database;
+use crate::deserialization::{self, GameJson};
+use crate::errors_manager::ProcessError;
+use crate::export_generator;
 use crate::games_info_generator::{self, get_opponents_and_their_rating, GameInfo};
 use crate::games_info_processor::{
+    compute_consistency_score, get_considered_games_with_differentials,
     get_half_time_differentials, process_average_time, process_flag_info, process_win_rate,
+    summarize_games, summarize_games_per_opponent_kind,
 };
-use crate::insight_generator::{self, InsightsPanelProps};
+use crate::insight_generator::{self, InsightMetrics, InsightsPanelProps};
+use crate::localization::{self, Message};
+use crate::long_think_generator;
+use crate::premove_generator;
+use crate::progress_generator::AnalysisSnapshot;
+use crate::rating_bucket_generator;
+use crate::report_generator;
 use crate::service_intermediary::{
-    ChessDataRequest, ChessDataResponse, GameFetchWarning, RequestSource,
+    ChartFormat, ChartKind, ChessDataRequest, ChessDataResponse, ExportFormat, GameFetchWarning,
+    GlobalFetchError, PlayerAnalysis, RequestSource,
 };
-use crate::trend_chart_generator;
+use crate::session_generator;
+use crate::svg_chart_generator;
+use crate::time_of_day_generator;
+use crate::tournament_generator;
+use crate::trend_chart_generator::{self, TrendChartSmoothing};
 use crate::util;
+use crate::variant;
 use crate::websocket::WebSocketSession;
 
-use actix_web::{Error, HttpResponse};
+use actix_web::{Error, HttpRequest, HttpResponse};
 use futures_util::TryStreamExt;
-use reqwest::Response;
-use serde_json;
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::{Response, StatusCode, Url};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::Mutex;
 use tokio_util::io::StreamReader;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
-    std::io::Error::new(std::io::ErrorKind::Other, err.to_string())
+    std::io::Error::other(err.to_string())
 }
 
-pub fn get_url(request_data: &ChessDataRequest) -> String {
+// Lichess asks to wait a full minute after a 429 response, see https://lichess.org/page/api-tips
+const RATE_LIMIT_INITIAL_BACKOFF_IN_SECONDS: u64 = 60;
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+const MAX_RATE_LIMIT_TOTAL_WAIT_IN_SECONDS: u64 = 180;
+const MAX_STREAM_RESUMPTIONS: u32 = 3;
+
+pub const LICHESS_BASE_URL: &str = "https://lichess.org";
+
+// Server-wide personal API token, used when the client does not forward its own.
+const LICHESS_TOKEN_ENV_VAR: &str = "LICHESS_API_TOKEN";
+
+// Personal API token. Authenticated requests have higher rate limits and include the private
+// games of the token owner. The value is redacted from the debug output so that it never
+// reaches the logs.
+#[derive(Clone, PartialEq)]
+pub struct LichessToken(String);
+
+impl std::fmt::Debug for LichessToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LichessToken(<redacted>)")
+    }
+}
+
+impl LichessToken {
+    fn new(token: &str) -> Option<Self> {
+        let token = token.trim();
+        (!token.is_empty()).then(|| LichessToken(token.to_string()))
+    }
+
+    // Clients forward their token as "Authorization: Bearer <token>".
+    pub fn from_request(req: &HttpRequest) -> Option<Self> {
+        req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|header_value| header_value.to_str().ok())
+            .and_then(|header_value| header_value.strip_prefix("Bearer "))
+            .and_then(LichessToken::new)
+    }
+
+    fn from_env() -> Option<Self> {
+        std::env::var(LICHESS_TOKEN_ENV_VAR)
+            .ok()
+            .and_then(|token| LichessToken::new(&token))
+    }
+}
+
+// The token forwarded with the request takes precedence over the server-wide one.
+fn get_token(request_data: &ChessDataRequest) -> Option<LichessToken> {
+    request_data
+        .lichess_token
+        .clone()
+        .or_else(LichessToken::from_env)
+}
+
+pub fn get_url(base_url: &str, request_data: &ChessDataRequest) -> String {
     // Note the color query parameter acts like a filter. If the user_color in the
     // request structure contains "both", we omit the color query parameter all together.
     format!(
-        "https://lichess.org/api/games/user/{}?max={}&perfType={}{}&rated=true&clocks=true",
+        "{}/api/games/user/{}?max={}&perfType={}{}{}&clocks=true",
+        base_url,
         request_data.username,
         request_data.games_count,
-        request_data.game_mode,
+        variant::get_perf_type(request_data),
         if request_data.user_color == "both" {
             String::new()
         } else {
             format!("&color={}", request_data.user_color)
+        },
+        // The lichess AI and anonymous players only play casual games.
+        if request_data.include_computer_opponents.unwrap_or(false)
+            || request_data.include_anonymous_opponents.unwrap_or(false)
+        {
+            ""
+        } else {
+            "&rated=true"
         }
     )
 }
@@ -55,55 +137,149 @@ pub async fn process_response_stream(
     let opt_websocket_addr_arc = Arc::new(Mutex::new(opt_websocket_addr.clone()));
     let game_idx_arc = Arc::new(Mutex::new(0));
 
-    let stream = request_response.bytes_stream().map_err(convert_err);
-    let stream_reader = StreamReader::new(stream);
-    let mut lines = BufReader::new(stream_reader).lines();
-
-    while let Some(line) = lines.next_line().await? {
-        let mut game_idx_lock = game_idx_arc.lock().await;
-        let username = request_data.username.clone();
-
-        match serde_json::from_str::<GameJson>(&line) {
-            Ok(game_json) => {
-                let mut games_info_lock = games_info_arc.lock().await;
-                games_info_lock.push(games_info_generator::generate(
-                    &game_json,
-                    &game_idx_lock,
-                    &username,
-                ));
-
-                // Notify client that one of the games requested has been processed (for loading bar).
-                if opt_websocket_addr.is_some() {
-                    let websocket_addr_lock = opt_websocket_addr_arc.lock().await;
-                    util::send_websocket_message(
-                        &websocket_addr_lock.clone().unwrap(),
-                        game_idx_lock.clone(),
-                        &request_data.games_count,
-                    );
-                }
+    let mut request_response = request_response;
+    let mut last_created_at: Option<u64> = None;
+    let mut resumptions_count = 0;
+    // The resumed stream starts at the creation time of the last game received, so the games
+    // created at that millisecond are sent again and skipped by their id.
+    let mut received_game_ids = HashSet::new();
+    let mut boundary_game_ids = HashSet::new();
+
+    loop {
+        let url = request_response.url().clone();
+        let stream = request_response.bytes_stream().map_err(convert_err);
+        let stream_reader = StreamReader::new(stream);
+        let mut lines = BufReader::new(stream_reader).lines();
+
+        // The stream is interrupted when the connection drops before lichess sent every game.
+        let is_interrupted = loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => break false,
+                Err(_) => break true,
+            };
+            let mut game_idx_lock = game_idx_arc.lock().await;
+            let username = request_data.username.clone();
+
+            match serde_json::from_str::<GameJson>(&line) {
+                Ok(game_json) => {
+                    if let Some(created_at) = game_json.created_at {
+                        if last_created_at != Some(created_at) {
+                            last_created_at = Some(created_at);
+                            boundary_game_ids.clear();
+                        }
+                        if let Some(game_id) = &game_json.id {
+                            boundary_game_ids.insert(game_id.clone());
+                        }
+                    }
+                    if let Some(game_id) = &game_json.id {
+                        if !received_game_ids.insert(game_id.clone()) {
+                            continue;
+                        }
+                    }
+
+                    // Games that cannot be analysed are reported with a warning, the others go on.
+                    match games_info_generator::generate(&game_json, &game_idx_lock, &username) {
+                        Ok(game_info) => {
+                            let excluded_game_warning =
+                                games_info_generator::get_excluded_game_warning(
+                                    &game_info,
+                                    request_data,
+                                );
+                            if let Some(warning) = excluded_game_warning {
+                                skipped_games_arc
+                                    .lock()
+                                    .await
+                                    .insert(*game_idx_lock, warning);
+                            }
+                            games_info_arc.lock().await.push(game_info);
+                        }
+                        Err(warning) => {
+                            skipped_games_arc
+                                .lock()
+                                .await
+                                .insert(*game_idx_lock, warning);
+                        }
+                    }
+
+                    // Notify client that one of the games requested has been processed (for loading bar).
+                    if opt_websocket_addr.is_some() {
+                        let websocket_addr_lock = opt_websocket_addr_arc.lock().await;
+                        util::send_websocket_message(
+                            &websocket_addr_lock.clone().unwrap(),
+                            *game_idx_lock,
+                            &request_data.games_count,
+                        );
+                    }
 
-                *game_idx_lock += 1;
+                    *game_idx_lock += 1;
+                }
+                Err(_) => {
+                    let mut skipped_games_lock = skipped_games_arc.lock().await;
+                    skipped_games_lock.entry(*game_idx_lock).or_insert_with(|| {
+                        GameFetchWarning::InternalErrorOccuredWhileProcessingAGame
+                    });
+                    *game_idx_lock += 1;
+                }
             }
-            Err(_) => {
-                let mut skipped_games_lock = skipped_games_arc.lock().await;
-                skipped_games_lock
-                    .entry(*game_idx_lock)
-                    .or_insert_with(|| GameFetchWarning::InternalErrorOccuredWhileProcessingAGame);
-                *game_idx_lock += 1;
+        };
+
+        let games_processed = *game_idx_arc.lock().await;
+        let remaining_games_count =
+            (request_data.games_count.max(0) as usize).saturating_sub(games_processed);
+        if !is_interrupted || remaining_games_count == 0 {
+            break;
+        }
+        if resumptions_count == MAX_STREAM_RESUMPTIONS {
+            return Err(ProcessError::FetchError {
+                message: Message::FetchFailed,
             }
+            .into());
         }
+
+        // The games sent again count towards the max of the resumed stream.
+        let resume_url = get_resume_url(
+            &url,
+            remaining_games_count + boundary_game_ids.len(),
+            last_created_at,
+        );
+        request_response = send_games_request(
+            resume_url.as_str(),
+            request_data,
+            games_processed,
+            opt_websocket_addr,
+        )
+        .await?;
+        if !request_response.status().is_success() {
+            return Err(get_unsuccessful_response_error(
+                request_data,
+                &request_response,
+            ));
+        }
+        resumptions_count += 1;
+    }
+
+    // An empty stream means that lichess has no game matching the requested filters, unless the
+    // connection dropped first and the resumed stream brought nothing either.
+    if *game_idx_arc.lock().await == 0 {
+        let error = if resumptions_count > 0 {
+            GlobalFetchError::StreamResumptionFailed
+        } else {
+            GlobalFetchError::RequestedMoreGamesThanAvailableInTheUserDatabase
+        };
+        return Err(ProcessError::GlobalFetchError { error }.into());
     }
 
     Ok(())
 }
 
 // TODO add specializations for this function
-pub async fn handle_successful_response(
+pub async fn generate_chess_data_response(
     request_data: &ChessDataRequest,
     requested_by: RequestSource,
     response: Response,
     opt_websocket_addr: &Option<Addr<WebSocketSession>>,
-) -> Result<HttpResponse, Error> {
+) -> Result<ChessDataResponse, Error> {
     let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
     let mut games_info: Vec<GameInfo> = Vec::new();
 
@@ -125,21 +301,23 @@ pub async fn handle_successful_response(
     // Note: Average time might be None if 0 games were kept for the computation.
     let average_time = process_average_time(&half_time_differentials);
 
-    // If the request was made internally for statistics, we only need to return the average time.
-    if requested_by == RequestSource::Internal {
-        match average_time {
-            Some(time) => {
-                return Ok(HttpResponse::Ok().json(ChessDataResponse::new_internal(
-                    time.to_string(),
-                    get_opponents_and_their_rating(&games_info),
-                )));
-            }
-            None => {
-                return Err(actix_web::error::ErrorInternalServerError(
-                    "Data processing was incomplete for the requested sample.",
-                ));
-            }
+    // Correspondence games have no half time differential but are analysed nonetheless, except
+    // for the internal statistics which only need the average time.
+    let correspondence = correspondence_generator::generate(&games_info, &skipped_games)
+        .filter(|_| requested_by == RequestSource::Frontend);
+    if average_time.is_none() && correspondence.is_none() {
+        return Err(ProcessError::GlobalFetchError {
+            error: GlobalFetchError::NotEnoughGamesToComputeAverage,
         }
+        .into());
+    }
+
+    // If the request was made internally for statistics, we only need to return the average time.
+    if let (RequestSource::Internal, Some(time)) = (requested_by, average_time) {
+        return Ok(ChessDataResponse::new_internal(
+            time.to_string(),
+            get_opponents_and_their_rating(&games_info),
+        ));
     }
 
     // =========== STEP 4: Process win rate ===========
@@ -148,53 +326,1106 @@ pub async fn handle_successful_response(
     // =========== STEP 5: Process flag info ===========
     let (user_flag_count, opponent_flag_cout) = process_flag_info(&games_info, &skipped_games);
 
-    // =========== STEP 6: Generate Trend Chart Data ===========
-    let trend_chart_data =
-        trend_chart_generator::generate(&games_info, &skipped_games, half_time_differentials);
+    // =========== STEP 6: Generate Opponent Rating, Session and Time of Day Breakdowns ===========
+    let considered_games = get_considered_games_with_differentials(
+        &games_info,
+        &skipped_games,
+        &half_time_differentials,
+    );
+    let opponent_rating_buckets = rating_bucket_generator::generate(&considered_games);
+    let opponent_kinds = summarize_games_per_opponent_kind(&considered_games);
+    let tournament = tournament_generator::generate(&considered_games);
+    let premoves = premove_generator::generate(&considered_games);
+    let sessions = session_generator::generate(
+        &considered_games,
+        session_generator::get_session_gap_in_minutes(request_data),
+    );
+    let time_of_day = time_of_day_generator::generate(
+        &considered_games,
+        time_of_day_generator::get_timezone(request_data)?,
+    );
+
+    // =========== STEP 7: Generate Trend Chart Data ===========
+    let trend_chart_data = trend_chart_generator::generate(
+        &games_info,
+        &skipped_games,
+        half_time_differentials,
+        &TrendChartSmoothing::from_request(request_data),
+    );
+    let trend_line = trend_chart_generator::compute_trend_line(&trend_chart_data);
+    let trend_chart_buckets = match request_data.trend_chart_bucket {
+        Some(bucket_size) => {
+            trend_chart_generator::generate_buckets(&trend_chart_data, bucket_size)
+        }
+        None => Vec::new(),
+    };
 
-    // =========== STEP 7: Generate Insights ===========
-    let insights: InsightsPanelProps =
-        insight_generator::get_insights(average_time, win_rate, request_data);
+    // =========== STEP 8: Generate Clock Curves ===========
+    let clock_curves = clock_curve_generator::generate_per_game(&games_info, &skipped_games);
+    let average_clock_curve = clock_curve_generator::generate_average(&games_info, &skipped_games);
+    let long_thinks = long_think_generator::generate(&games_info, &skipped_games);
+
+    // =========== STEP 9: Generate Insights ===========
+    let overall_summary = summarize_games(&considered_games);
+    let locale = localization::get_locale(request_data);
+    let consistency = compute_consistency_score(&considered_games, locale);
+    let game_variant = variant::get_variant(request_data);
+    let insights: InsightsPanelProps = insight_generator::get_insights(
+        win_rate,
+        &InsightMetrics {
+            username: &request_data.username,
+            locale,
+            variant: game_variant,
+            average_time,
+            overall_summary: &overall_summary,
+            average_clock_curve: &average_clock_curve,
+            opponent_rating_buckets: &opponent_rating_buckets,
+            sessions: &sessions,
+            time_of_day: &time_of_day,
+            trend_line: trend_line.as_ref(),
+            correspondence: correspondence.as_ref(),
+            premoves: &premoves,
+        },
+    );
 
     // For UI testing purposes:
     //    Adding a bunch of games with error message for errors side panel
     // util::generate_dummy_erros_testing(&mut skipped_games);
 
-    Ok(HttpResponse::Ok().json(ChessDataResponse::new(
-        insights.average_time,
-        insights.explanation_message,
-        skipped_games,
+    Ok(ChessDataResponse::new(PlayerAnalysis {
+        variant: game_variant,
+        time: insights.average_time,
+        explanation_message: insights.explanation_message,
+        insights: insights.insights,
+        games_with_errors: deserialization::convert_games_with_errors_to_displayable_format(
+            skipped_games,
+            locale,
+        ),
         trend_chart_data,
-        insights.win_ratio,
-        (user_flag_count, opponent_flag_cout),
-    )))
+        trend_line,
+        trend_chart_buckets,
+        clock_curves,
+        average_clock_curve,
+        opponent_rating_buckets,
+        opponent_kinds,
+        tournament,
+        premoves,
+        long_thinks,
+        consistency,
+        sessions,
+        time_of_day,
+        player_win_rate_in_fetched_games: insights.win_ratio,
+        players_flag_counts: (user_flag_count, opponent_flag_cout),
+        analysed_games_count: considered_games.len(),
+        correspondence,
+    }))
 }
 
-pub async fn fetch_player_data(
+// Snapshots are kept per user so that the progress between analyses can be followed, the
+// previous consistency score of the same perf type and color is returned along the new one.
+fn store_analysis_snapshot(
+    database_path: &str,
+    request_data: &ChessDataRequest,
+    player_analysis: &mut PlayerAnalysis,
+) -> rusqlite::Result<()> {
+    let perf_type = variant::get_perf_type(request_data);
+
+    let previous_snapshots =
+        database::get_analysis_snapshots(database_path, &request_data.username, &perf_type)?;
+    if let Some(consistency) = player_analysis.consistency.as_mut() {
+        consistency.previous_score = previous_snapshots
+            .iter()
+            .rev()
+            .filter(|snapshot| snapshot.user_color == request_data.user_color)
+            .find_map(|snapshot| snapshot.consistency_score);
+    }
+
+    let (user_flag_count, opponent_flag_count) = player_analysis.players_flag_counts;
+    let analysed_games_count = player_analysis.analysed_games_count;
+    let get_flag_rate = |flag_count: i32| {
+        (analysed_games_count > 0).then(|| flag_count as f32 / analysed_games_count as f32)
+    };
+    database::log_analysis_snapshot(
+        database_path,
+        &request_data.username,
+        &AnalysisSnapshot {
+            created_at: chrono::Utc::now().timestamp_millis(),
+            perf_type,
+            user_color: request_data.user_color.clone(),
+            games_count: request_data.games_count,
+            analysed_games_count: analysed_games_count as i32,
+            average_time: player_analysis.time.parse().ok(),
+            win_rate: player_analysis
+                .player_win_rate_in_fetched_games
+                .parse()
+                .unwrap_or(0.0),
+            user_flag_rate: get_flag_rate(user_flag_count),
+            opponent_flag_rate: get_flag_rate(opponent_flag_count),
+            consistency_score: player_analysis
+                .consistency
+                .as_ref()
+                .map(|consistency| consistency.score),
+        },
+    )
+}
+
+pub async fn handle_successful_response(
+    database_path: &str,
     request_data: &ChessDataRequest,
     requested_by: RequestSource,
+    response: Response,
     opt_websocket_addr: &Option<Addr<WebSocketSession>>,
 ) -> Result<HttpResponse, Error> {
-    let url = get_url(&request_data);
+    let mut chess_data_response =
+        generate_chess_data_response(request_data, requested_by, response, opt_websocket_addr)
+            .await?;
+    // The snapshot only feeds the progress endpoint, the analysis is returned even if it could
+    // not be stored.
+    if let ChessDataResponse::RequestFromFrontend(player_analysis) = &mut chess_data_response {
+        if let Err(error) = store_analysis_snapshot(database_path, request_data, player_analysis) {
+            log::warn!(
+                "The analysis snapshot of {} could not be stored: {}",
+                request_data.username,
+                error
+            );
+        }
+    }
+    Ok(HttpResponse::Ok().json(chess_data_response))
+}
+
+pub async fn handle_successful_report_response(
+    request_data: &ChessDataRequest,
+    response: Response,
+) -> Result<HttpResponse, Error> {
+    // Reports are downloaded directly, there is no loading bar to notify.
+    let chess_data_response =
+        generate_chess_data_response(request_data, RequestSource::Frontend, response, &None)
+            .await?;
+    let body = report_generator::generate(request_data, &chess_data_response)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}_{}_report.html\"",
+                request_data.username,
+                variant::get_perf_type(request_data)
+            ),
+        ))
+        .body(body))
+}
+
+pub async fn handle_successful_export_response(
+    request_data: &ChessDataRequest,
+    response: Response,
+    export_format: ExportFormat,
+) -> Result<HttpResponse, Error> {
+    let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
+    let mut games_info: Vec<GameInfo> = Vec::new();
+
+    // Exports are downloaded directly, there is no loading bar to notify.
+    process_response_stream(
+        &mut games_info,
+        request_data,
+        response,
+        &mut skipped_games,
+        &None,
+    )
+    .await?;
+
+    let half_time_differentials: Vec<f32> =
+        get_half_time_differentials(&games_info, &mut skipped_games, false);
+
+    let exported_rows = export_generator::generate(
+        &games_info,
+        &skipped_games,
+        &half_time_differentials,
+        localization::get_locale(request_data),
+    );
+    let body = export_generator::serialize(&exported_rows, export_format)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(export_format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}_{}.{}\"",
+                request_data.username,
+                variant::get_perf_type(request_data),
+                export_format.file_extension()
+            ),
+        ))
+        .body(body))
+}
+
+pub async fn handle_successful_chart_response(
+    request_data: &ChessDataRequest,
+    response: Response,
+    chart_kind: ChartKind,
+    chart_format: ChartFormat,
+) -> Result<HttpResponse, Error> {
+    let mut skipped_games: HashMap<usize, GameFetchWarning> = HashMap::new();
+    let mut games_info: Vec<GameInfo> = Vec::new();
+
+    // Charts are embedded as images, there is no loading bar to notify.
+    process_response_stream(
+        &mut games_info,
+        request_data,
+        response,
+        &mut skipped_games,
+        &None,
+    )
+    .await?;
+
+    let half_time_differentials: Vec<f32> =
+        get_half_time_differentials(&games_info, &mut skipped_games, false);
+
+    let svg = match chart_kind {
+        ChartKind::Trend => svg_chart_generator::generate_trend_chart(
+            &trend_chart_generator::generate(
+                &games_info,
+                &skipped_games,
+                half_time_differentials,
+                &TrendChartSmoothing::from_request(request_data),
+            ),
+            localization::get_locale(request_data),
+        )?,
+        ChartKind::WinRate => svg_chart_generator::generate_win_rate_donut_chart(
+            process_win_rate(&games_info, &skipped_games),
+        )?,
+        ChartKind::ClockCurve => svg_chart_generator::generate_clock_curve_chart(
+            &clock_curve_generator::generate_average(&games_info, &skipped_games),
+            localization::get_locale(request_data),
+        )?,
+    };
+
+    let body = match chart_format {
+        ChartFormat::Svg => svg.into_bytes(),
+        ChartFormat::Png => svg_chart_generator::rasterize_to_png(&svg)?,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(chart_format.content_type())
+        .body(body))
+}
+
+// The Retry-After header takes precedence, otherwise the wait doubles after each attempt.
+fn get_rate_limit_backoff(retry_after: Option<&str>, attempt: u32) -> Duration {
+    retry_after
+        .and_then(|retry_after| retry_after.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| {
+            Duration::from_secs(RATE_LIMIT_INITIAL_BACKOFF_IN_SECONDS * 2u64.pow(attempt))
+        })
+}
+
+// Lichess streams the most recent games first, so an interrupted stream resumes with the games
+// created strictly before the last received one.
+fn get_resume_url(url: &Url, remaining_games_count: usize, last_created_at: Option<u64>) -> Url {
+    let query_pairs = url
+        .query_pairs()
+        .filter(|(key, _)| key != "max" && key != "until")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<(String, String)>>();
+
+    let mut resume_url = url.clone();
+    {
+        let mut resume_query_pairs = resume_url.query_pairs_mut();
+        resume_query_pairs
+            .clear()
+            .extend_pairs(query_pairs)
+            .append_pair("max", &remaining_games_count.to_string());
+        if let Some(created_at) = last_created_at {
+            resume_query_pairs.append_pair("until", &created_at.to_string());
+        }
+    }
+    resume_url
+}
+
+// Rate limited requests are retried, the wait is surfaced through the websocket progress
+// channel. The 429 response is returned once the retries are exhausted or when the next wait
+// would exceed the total wait allowed.
+async fn send_games_request(
+    url: &str,
+    request_data: &ChessDataRequest,
+    games_processed: usize,
+    opt_websocket_addr: &Option<Addr<WebSocketSession>>,
+) -> Result<Response, Error> {
     let client = reqwest::Client::new();
+    let token = get_token(request_data);
+    let mut attempt = 0;
+    let mut total_wait = Duration::ZERO;
+
+    loop {
+        let mut request = client.get(url).header("Accept", "application/x-ndjson");
+        if let Some(LichessToken(token)) = &token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(ProcessError::from)?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS || attempt == MAX_RATE_LIMIT_RETRIES {
+            return Ok(response);
+        }
+
+        let backoff = get_rate_limit_backoff(
+            response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|header_value| header_value.to_str().ok()),
+            attempt,
+        );
+        total_wait += backoff;
+        if total_wait > Duration::from_secs(MAX_RATE_LIMIT_TOTAL_WAIT_IN_SECONDS) {
+            return Ok(response);
+        }
+        attempt += 1;
+
+        if let Some(websocket_addr) = opt_websocket_addr {
+            util::send_websocket_rate_limit_message(
+                websocket_addr,
+                games_processed,
+                &request_data.games_count,
+                backoff,
+                attempt,
+                MAX_RATE_LIMIT_RETRIES,
+            );
+        }
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+fn get_unsuccessful_response_error(request_data: &ChessDataRequest, response: &Response) -> Error {
+    let message = match response.status() {
+        StatusCode::NOT_FOUND => Message::UserNotFound {
+            username: request_data.username.clone(),
+        },
+        StatusCode::TOO_MANY_REQUESTS => Message::RateLimited,
+        _ => Message::LichessUnavailable,
+    };
+    ProcessError::FetchError { message }.into()
+}
 
-    let response = client
-        .get(&url)
-        .header("Accept", "application/x-ndjson")
-        .send()
+pub async fn fetch_player_data(
+    base_url: &str,
+    database_path: &str,
+    request_data: &ChessDataRequest,
+    requested_by: RequestSource,
+    opt_websocket_addr: &Option<Addr<WebSocketSession>>,
+) -> Result<HttpResponse, Error> {
+    let url = get_url(base_url, request_data);
+    let response = send_games_request(&url, request_data, 0, opt_websocket_addr).await?;
+
+    if response.status().is_success() {
+        handle_successful_response(
+            database_path,
+            request_data,
+            requested_by,
+            response,
+            opt_websocket_addr,
+        )
         .await
-        .map_err(|e| {
-            actix_web::error::ErrorInternalServerError(format!("Request error: {:?}", e))
-        })?;
+    } else {
+        Err(get_unsuccessful_response_error(request_data, &response))
+    }
+}
+
+pub async fn export_player_data(
+    base_url: &str,
+    request_data: &ChessDataRequest,
+    export_format: ExportFormat,
+) -> Result<HttpResponse, Error> {
+    let url = get_url(base_url, request_data);
+    let response = send_games_request(&url, request_data, 0, &None).await?;
+
+    if response.status().is_success() {
+        handle_successful_export_response(request_data, response, export_format).await
+    } else {
+        Err(get_unsuccessful_response_error(request_data, &response))
+    }
+}
+
+pub async fn report_player_data(
+    base_url: &str,
+    request_data: &ChessDataRequest,
+) -> Result<HttpResponse, Error> {
+    let url = get_url(base_url, request_data);
+    let response = send_games_request(&url, request_data, 0, &None).await?;
+
+    if response.status().is_success() {
+        handle_successful_report_response(request_data, response).await
+    } else {
+        Err(get_unsuccessful_response_error(request_data, &response))
+    }
+}
+
+pub async fn chart_player_data(
+    base_url: &str,
+    request_data: &ChessDataRequest,
+    chart_kind: ChartKind,
+    chart_format: ChartFormat,
+) -> Result<HttpResponse, Error> {
+    let url = get_url(base_url, request_data);
+    let response = send_games_request(&url, request_data, 0, &None).await?;
 
     if response.status().is_success() {
-        handle_successful_response(request_data, requested_by, response, opt_websocket_addr).await
+        handle_successful_chart_response(request_data, response, chart_kind, chart_format).await
     } else {
-        let status = response.status();
-        let error_message = response
-            .text()
+        Err(get_unsuccessful_response_error(request_data, &response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+    use crate::errors_manager::ErrorCode;
+    use crate::games_info_generator::OpponentKind;
+    use crate::mock_lichess_server::{MockGameLine, MockLichessServer, MockUser};
+    use crate::unit_test_util;
+
+    use std::path::Path;
+
+    // Golden files are rewritten rather than compared when this variable is set, so that
+    // analysis changes are reviewed through the diff of the golden files.
+    const UPDATE_GOLDEN_ENV_VAR: &str = "UPDATE_GOLDEN";
+    const MAX_DIFF_LINES_DISPLAYED: usize = 20;
+
+    // Lists the lines that differ by line number, e.g "- 12: expected" then "+ 12: actual".
+    fn get_line_diff(expected: &str, actual: &str) -> Option<String> {
+        let expected_lines = expected.lines().collect::<Vec<&str>>();
+        let actual_lines = actual.lines().collect::<Vec<&str>>();
+
+        let diff_lines = (0..expected_lines.len().max(actual_lines.len()))
+            .filter(|&i| expected_lines.get(i) != actual_lines.get(i))
+            .flat_map(|i| {
+                let expected_line = expected_lines
+                    .get(i)
+                    .map(|line| format!("- {}: {}", i + 1, line));
+                let actual_line = actual_lines
+                    .get(i)
+                    .map(|line| format!("+ {}: {}", i + 1, line));
+                expected_line.into_iter().chain(actual_line)
+            })
+            .collect::<Vec<String>>();
+
+        if diff_lines.is_empty() {
+            return None;
+        }
+        let hidden_lines_count = diff_lines.len().saturating_sub(MAX_DIFF_LINES_DISPLAYED);
+        let mut diff = diff_lines
+            .into_iter()
+            .take(MAX_DIFF_LINES_DISPLAYED)
+            .collect::<Vec<String>>()
+            .join("\n");
+        if hidden_lines_count > 0 {
+            diff += &format!("\n... {} more lines", hidden_lines_count);
+        }
+        Some(diff)
+    }
+
+    #[test]
+    fn test_get_rate_limit_backoff() {
+        assert_eq!(get_rate_limit_backoff(Some("5"), 2), Duration::from_secs(5));
+        assert_eq!(get_rate_limit_backoff(None, 0), Duration::from_secs(60));
+        assert_eq!(
+            get_rate_limit_backoff(Some("soon"), 2),
+            Duration::from_secs(240)
+        );
+    }
+
+    #[test]
+    fn test_lichess_token_from_request() {
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((AUTHORIZATION, "Bearer lip_secret"))
+            .to_http_request();
+        let token = LichessToken::from_request(&req).unwrap();
+        assert_eq!(token, LichessToken(String::from("lip_secret")));
+        assert!(!format!("{:?}", token).contains("lip_secret"));
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((AUTHORIZATION, "Bearer "))
+            .to_http_request();
+        assert_eq!(LichessToken::from_request(&req), None);
+    }
+
+    #[test]
+    fn test_get_url() {
+        let mut request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 20,
+            game_mode: String::from("blitz"),
+            user_color: String::from("white"),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_url(LICHESS_BASE_URL, &request_data),
+            "https://lichess.org/api/games/user/user?max=20&perfType=blitz&color=white&rated=true\
+             &clocks=true"
+        );
+
+        request_data.variant = Some(variant::Variant::Chess960);
+        assert_eq!(
+            get_url(LICHESS_BASE_URL, &request_data),
+            "https://lichess.org/api/games/user/user?max=20&perfType=chess960&color=white\
+             &rated=true&clocks=true"
+        );
+
+        request_data.include_computer_opponents = Some(true);
+        assert_eq!(
+            get_url(LICHESS_BASE_URL, &request_data),
+            "https://lichess.org/api/games/user/user?max=20&perfType=chess960&color=white\
+             &clocks=true"
+        );
+    }
+
+    #[test]
+    fn test_get_resume_url() {
+        let url =
+            Url::parse("https://lichess.org/api/games/user/user?max=50&perfType=blitz").unwrap();
+        assert_eq!(
+            get_resume_url(&url, 20, Some(1000)).as_str(),
+            "https://lichess.org/api/games/user/user?perfType=blitz&max=20&until=1000"
+        );
+        assert_eq!(
+            get_resume_url(&url, 50, None).as_str(),
+            "https://lichess.org/api/games/user/user?perfType=blitz&max=50"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_process_response_stream_resumes_after_rate_limit() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: [3000, 2000, 1000]
+                    .map(|created_at| {
+                        MockGameLine::game(&unit_test_util::get_some_mocked_complete_game(
+                            created_at,
+                        ))
+                    })
+                    .into(),
+                truncate_after: Some(2),
+                rate_limited_requests: vec![1],
+                ..Default::default()
+            },
+        )]);
+
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 3,
+            ..Default::default()
+        };
+        let url = format!("{}/api/games/user/user?max=3", mock_lichess.get_base_url());
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+
+        let mut games_info = Vec::new();
+        let mut skipped_games = HashMap::new();
+        process_response_stream(
+            &mut games_info,
+            &request_data,
+            response,
+            &mut skipped_games,
+            &None,
+        )
+        .await
+        .unwrap();
+
+        let created_ats = games_info
+            .iter()
+            .map(|game_info| game_info.created_at)
+            .collect::<Vec<Option<u64>>>();
+        assert_eq!(created_ats, [Some(3000), Some(2000), Some(1000)]);
+        assert!(skipped_games.is_empty());
+
+        let requests = mock_lichess.get_requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].ends_with("max=2&until=2000"));
+        assert_eq!(requests[1], requests[2]);
+    }
+
+    #[actix_web::test]
+    async fn test_send_games_request_caps_rate_limit_wait() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                rate_limited_requests: vec![0],
+                retry_after_in_seconds: MAX_RATE_LIMIT_TOTAL_WAIT_IN_SECONDS + 1,
+                ..Default::default()
+            },
+        )]);
+
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 1,
+            ..Default::default()
+        };
+        let url = format!("{}/api/games/user/user?max=1", mock_lichess.get_base_url());
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(mock_lichess.get_requests().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_process_response_stream_skips_games_received_twice() {
+        // The stream drops between two games created at the same millisecond, the resumed one
+        // sends the first of them again.
+        let mut games = [3000, 2000, 2000, 1000].map(unit_test_util::get_some_mocked_complete_game);
+        games[2].id = Some(String::from("game2000bis"));
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: games.iter().map(MockGameLine::game).collect(),
+                truncate_after: Some(2),
+                ..Default::default()
+            },
+        )]);
+
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 4,
+            ..Default::default()
+        };
+        let url = format!("{}/api/games/user/user?max=4", mock_lichess.get_base_url());
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+
+        let mut games_info = Vec::new();
+        let mut skipped_games = HashMap::new();
+        process_response_stream(
+            &mut games_info,
+            &request_data,
+            response,
+            &mut skipped_games,
+            &None,
+        )
+        .await
+        .unwrap();
+
+        let created_ats = games_info
+            .iter()
+            .map(|game_info| game_info.created_at)
+            .collect::<Vec<Option<u64>>>();
+        assert_eq!(
+            created_ats,
+            [Some(3000), Some(2000), Some(2000), Some(1000)]
+        );
+        assert!(skipped_games.is_empty());
+
+        let requests = mock_lichess.get_requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].ends_with("max=3&until=2000"));
+    }
+
+    #[actix_web::test]
+    async fn test_process_response_stream_reports_failed_resumption() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: vec![MockGameLine::game(
+                    &unit_test_util::get_some_mocked_complete_game(1000),
+                )],
+                truncate_after: Some(0),
+                empty_after_truncation: true,
+                ..Default::default()
+            },
+        )]);
+
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 1,
+            ..Default::default()
+        };
+        let url = format!("{}/api/games/user/user?max=1", mock_lichess.get_base_url());
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+
+        let mut games_info = Vec::new();
+        let mut skipped_games = HashMap::new();
+        let error = process_response_stream(
+            &mut games_info,
+            &request_data,
+            response,
+            &mut skipped_games,
+            &None,
+        )
+        .await
+        .unwrap_err();
+
+        let process_error = error.as_error::<ProcessError>().unwrap();
+        assert_eq!(process_error.get_code(), ErrorCode::StreamResumptionFailed);
+        assert_eq!(mock_lichess.get_requests().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_process_response_stream_excludes_computer_and_anonymous_opponents() {
+        // The user plays black, the white player is a human, a bot account, the lichess AI or
+        // anonymous. The last game is a casual game against a human.
+        let mut bot_game = unit_test_util::get_some_mocked_complete_game(2500);
+        let white_player = bot_game.players.as_mut().unwrap().white.as_mut().unwrap();
+        white_player.user.as_mut().unwrap().title = Some(String::from("BOT"));
+        let mut casual_game = unit_test_util::get_some_mocked_complete_game(500);
+        casual_game.rated = Some(false);
+        let mut computer_game = unit_test_util::get_some_mocked_complete_game(2000);
+        let mut anonymous_game = unit_test_util::get_some_mocked_complete_game(1000);
+        for (game, ai_level) in [(&mut computer_game, Some(3)), (&mut anonymous_game, None)] {
+            game.players.as_mut().unwrap().white = Some(deserialization::PlayerDetail {
+                ai_level,
+                berserk: None,
+                rating: None,
+                rating_diff: None,
+                user: None,
+            });
+        }
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: vec![
+                    MockGameLine::game(&unit_test_util::get_some_mocked_complete_game(3000)),
+                    MockGameLine::game(&bot_game),
+                    MockGameLine::game(&computer_game),
+                    MockGameLine::game(&anonymous_game),
+                    MockGameLine::game(&casual_game),
+                ],
+                ..Default::default()
+            },
+        )]);
+
+        let mut request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 5,
+            ..Default::default()
+        };
+        let url = format!("{}/api/games/user/user?max=5", mock_lichess.get_base_url());
+        for (include_computer_opponents, expected_skipped_games) in [
+            (
+                None,
+                HashMap::from([
+                    (2, GameFetchWarning::ComputerOpponent),
+                    (3, GameFetchWarning::AnonymousOpponent),
+                    (4, GameFetchWarning::CasualGame),
+                ]),
+            ),
+            (
+                Some(true),
+                HashMap::from([
+                    (3, GameFetchWarning::AnonymousOpponent),
+                    (4, GameFetchWarning::CasualGame),
+                ]),
+            ),
+        ] {
+            request_data.include_computer_opponents = include_computer_opponents;
+            let response = send_games_request(&url, &request_data, 0, &None)
+                .await
+                .unwrap();
+
+            let mut games_info = Vec::new();
+            let mut skipped_games = HashMap::new();
+            process_response_stream(
+                &mut games_info,
+                &request_data,
+                response,
+                &mut skipped_games,
+                &None,
+            )
+            .await
+            .unwrap();
+
+            let opponents = games_info
+                .iter()
+                .map(|game_info| {
+                    (
+                        game_info.opponent_kind,
+                        game_info.opponent_username.as_str(),
+                    )
+                })
+                .collect::<Vec<(OpponentKind, &str)>>();
+            assert_eq!(
+                opponents,
+                [
+                    (OpponentKind::Human, "other_user"),
+                    (OpponentKind::Bot, "other_user"),
+                    (OpponentKind::Computer, "Stockfish level 3"),
+                    (OpponentKind::Anonymous, "Anonymous"),
+                    (OpponentKind::Human, "other_user"),
+                ]
+            );
+            assert_eq!(skipped_games, expected_skipped_games);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_store_analysis_snapshot() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: [3000, 2000, 1000]
+                    .map(|created_at| {
+                        MockGameLine::game(&unit_test_util::get_some_mocked_complete_game(
+                            created_at,
+                        ))
+                    })
+                    .into(),
+                ..Default::default()
+            },
+        )]);
+        let mut request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 3,
+            game_mode: String::from("blitz"),
+            user_color: String::from("both"),
+            ..Default::default()
+        };
+        let url = get_url(&mock_lichess.get_base_url(), &request_data);
+        let get_player_analysis = || async {
+            let response = send_games_request(&url, &request_data, 0, &None)
+                .await
+                .unwrap();
+            match generate_chess_data_response(
+                &request_data,
+                RequestSource::Frontend,
+                response,
+                &None,
+            )
+            .await
+            .unwrap()
+            {
+                ChessDataResponse::RequestFromFrontend(player_analysis) => player_analysis,
+                _ => panic!("Expected the analysis of the frontend"),
+            }
+        };
+        let mut first_analysis = get_player_analysis().await;
+        let mut second_analysis = get_player_analysis().await;
+        let mut third_analysis = get_player_analysis().await;
+
+        let database = TestDatabase::create();
+        request_data.username = String::from("User");
+        store_analysis_snapshot(&database.path, &request_data, &mut first_analysis).unwrap();
+        let first_consistency = first_analysis.consistency.as_ref().unwrap();
+        assert_eq!(first_consistency.previous_score, None);
+        store_analysis_snapshot(&database.path, &request_data, &mut second_analysis).unwrap();
+        assert_eq!(
+            second_analysis.consistency.as_ref().unwrap().previous_score,
+            Some(first_consistency.score)
+        );
+        // The scores of the games played as both colors are not compared with the white ones.
+        request_data.user_color = String::from("white");
+        store_analysis_snapshot(&database.path, &request_data, &mut third_analysis).unwrap();
+        assert_eq!(
+            third_analysis.consistency.as_ref().unwrap().previous_score,
+            None
+        );
+
+        let snapshots = database::get_analysis_snapshots(&database.path, "user", "blitz").unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].user_color, "both");
+        assert_eq!(snapshots[0].games_count, 3);
+        assert_eq!(snapshots[0].analysed_games_count, 3);
+        assert_eq!(
+            snapshots[0].average_time,
+            first_analysis.time.parse::<f32>().ok()
+        );
+        assert_eq!(
+            Some(snapshots[0].win_rate),
+            first_analysis.player_win_rate_in_fetched_games.parse().ok()
+        );
+        assert_eq!(
+            snapshots[0].consistency_score,
+            Some(first_consistency.score)
+        );
+        let (user_flag_count, opponent_flag_count) = second_analysis.players_flag_counts;
+        assert_eq!(
+            (snapshots[1].user_flag_rate, snapshots[1].opponent_flag_rate),
+            (
+                Some(user_flag_count as f32 / 3.0),
+                Some(opponent_flag_count as f32 / 3.0)
+            )
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_handle_successful_response_without_database() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: vec![MockGameLine::game(
+                    &unit_test_util::get_some_mocked_complete_game(1000),
+                )],
+                ..Default::default()
+            },
+        )]);
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 1,
+            game_mode: String::from("blitz"),
+            user_color: String::from("both"),
+            ..Default::default()
+        };
+        let url = get_url(&mock_lichess.get_base_url(), &request_data);
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+
+        // The snapshot cannot be stored in a missing directory, the analysis is still returned.
+        let database_path = std::env::temp_dir()
+            .join(uuid::Uuid::new_v4().to_string())
+            .join("request_timing_data.db");
+        let http_response = handle_successful_response(
+            &database_path.to_string_lossy(),
+            &request_data,
+            RequestSource::Frontend,
+            response,
+            &None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(http_response.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_generate_chess_data_response_correspondence() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: (0..3)
+                    .map(|game_idx| {
+                        let mut game = unit_test_util::get_some_mocked_correspondence_game(3);
+                        game.id = Some(format!("game{}", game_idx));
+                        MockGameLine::game(&game)
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        )]);
+
+        let request_data = ChessDataRequest {
+            username: String::from("user"),
+            games_count: 3,
+            game_mode: String::from("correspondence"),
+            user_color: String::from("both"),
+            ..Default::default()
+        };
+        let url = get_url(&mock_lichess.get_base_url(), &request_data);
+
+        // Internal statistics need the half time differential that daily games do not have.
+        let response = send_games_request(&url, &request_data, 0, &None)
             .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Ok(HttpResponse::build(status).json(serde_json::json!({ "error": error_message })))
+            .unwrap();
+        assert!(generate_chess_data_response(
+            &request_data,
+            RequestSource::Internal,
+            response,
+            &None
+        )
+        .await
+        .is_err());
+
+        let response = send_games_request(&url, &request_data, 0, &None)
+            .await
+            .unwrap();
+        let ChessDataResponse::RequestFromFrontend(player_analysis) =
+            generate_chess_data_response(&request_data, RequestSource::Frontend, response, &None)
+                .await
+                .unwrap()
+        else {
+            panic!("Expected the analysis of the frontend");
+        };
+
+        let correspondence = player_analysis.correspondence.as_ref().unwrap();
+        assert_eq!(correspondence.games_count, 3);
+        assert_eq!(correspondence.average_hours_per_move, Some(3.0));
+        assert_eq!(
+            player_analysis.explanation_message.0,
+            "In the correspondence games of user, a move takes 3.00 hours on average, 4% of the \
+             time allowed per move."
+        );
+        assert_eq!(player_analysis.games_with_errors.len(), 3);
+        assert_eq!(
+            player_analysis.games_with_errors[0].1,
+            "Game has no clock data, it is only used for the correspondence analysis."
+        );
+    }
+
+    // Runs the full analysis on every recorded export of fixtures/games and compares the
+    // response with its snapshot in fixtures/golden.
+    #[actix_web::test]
+    async fn test_fixture_corpus_matches_golden_outputs() {
+        let fixtures_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let is_updating_golden = std::env::var_os(UPDATE_GOLDEN_ENV_VAR).is_some();
+
+        let mut fixture_paths = std::fs::read_dir(fixtures_dir.join("games"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "ndjson")
+            })
+            .collect::<Vec<_>>();
+        fixture_paths.sort();
+        assert!(!fixture_paths.is_empty());
+
+        let mut mismatches = Vec::new();
+        for fixture_path in fixture_paths {
+            let fixture_name = fixture_path
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            let export = std::fs::read_to_string(&fixture_path).unwrap();
+            let mock_lichess = MockLichessServer::start(vec![(
+                "user",
+                MockUser {
+                    games: export.lines().map(MockGameLine::recorded).collect(),
+                    ..Default::default()
+                },
+            )]);
+
+            let request_data = ChessDataRequest {
+                username: String::from("user"),
+                games_count: 50,
+                game_mode: String::from("blitz"),
+                user_color: String::from("both"),
+                ..Default::default()
+            };
+            let url = get_url(&mock_lichess.get_base_url(), &request_data);
+            let response = send_games_request(&url, &request_data, 0, &None)
+                .await
+                .unwrap();
+            let chess_data_response = generate_chess_data_response(
+                &request_data,
+                RequestSource::Frontend,
+                response,
+                &None,
+            )
+            .await
+            .unwrap();
+            let actual = serde_json::to_string_pretty(&chess_data_response).unwrap() + "\n";
+
+            let golden_path = fixtures_dir
+                .join("golden")
+                .join(format!("{}.json", fixture_name));
+            if is_updating_golden {
+                std::fs::write(&golden_path, actual).unwrap();
+                continue;
+            }
+            let expected = std::fs::read_to_string(&golden_path).unwrap_or_default();
+            if let Some(diff) = get_line_diff(&expected, &actual) {
+                mismatches.push(format!("{}:\n{}", fixture_name, diff));
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "The analysis differs from the golden outputs. Review the changes and rerun with {}=1 \
+             to accept them.\n{}",
+            UPDATE_GOLDEN_ENV_VAR,
+            mismatches.join("\n\n")
+        );
     }
 }
@@ -0,0 +1,1337 @@
+use crate::service_intermediary::ChessDataRequest;
+
+use actix_web::HttpRequest;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+    Es,
+}
+
+impl Locale {
+    // Only the primary subtag matters, e.g "fr-CA" is French.
+    pub fn from_language_tag(language_tag: &str) -> Option<Self> {
+        let primary_subtag = language_tag.trim().split(['-', '_']).next()?;
+        match primary_subtag.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::En),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    // Value of the lang attribute of the HTML reports.
+    pub fn get_language_tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+            Locale::Es => "es",
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            Locale::En => '.',
+            Locale::Fr | Locale::Es => ',',
+        }
+    }
+}
+
+// Picks the supported language with the highest quality value, e.g "de-DE, fr;q=0.8, en;q=0.5"
+// resolves to French.
+pub fn parse_accept_language(accept_language: &str) -> Option<Locale> {
+    let mut weighted_locales = accept_language
+        .split(',')
+        .filter_map(|language_range| {
+            let mut parameters = language_range.split(';');
+            let locale = Locale::from_language_tag(parameters.next()?)?;
+            let quality = parameters
+                .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then_some((locale, quality))
+        })
+        .collect::<Vec<(Locale, f32)>>();
+
+    // Stable sort: the first listed language wins among equal quality values.
+    weighted_locales.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    weighted_locales.first().map(|(locale, _)| *locale)
+}
+
+// The locale explicitly set in the request takes precedence over the Accept-Language header.
+pub fn resolve_locale(request_locale: Option<Locale>, req: &HttpRequest) -> Locale {
+    request_locale
+        .or_else(|| {
+            req.headers()
+                .get("accept-language")
+                .and_then(|header_value| header_value.to_str().ok())
+                .and_then(parse_accept_language)
+        })
+        .unwrap_or_default()
+}
+
+pub fn get_locale(request_data: &ChessDataRequest) -> Locale {
+    request_data.locale.unwrap_or_default()
+}
+
+fn format_decimal(value: f32, locale: Locale) -> String {
+    format!("{:.2}", value).replace('.', &locale.decimal_separator().to_string())
+}
+
+// Every message displayed to the user. Parameters are formatted according to the locale.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    // Insights
+    TimeNotComputed,
+    AverageTimeBehind {
+        username: String,
+        seconds: f32,
+    },
+    AverageTimeAhead {
+        username: String,
+        seconds: f32,
+    },
+    AverageTimeEqual {
+        username: String,
+    },
+    SteadyPaceSuggestion,
+    GamesLostOnTime {
+        username: String,
+        percentage: f32,
+    },
+    TimeTroubleSuggestion,
+    OpeningTimeDeficit {
+        username: String,
+        moves_count: usize,
+        seconds: f32,
+    },
+    OpeningPreparationSuggestion,
+    HigherRatedOpponentsSlowdown {
+        username: String,
+        seconds: f32,
+    },
+    HigherRatedOpponentsSuggestion,
+    SpeedUpAfterLosses {
+        username: String,
+    },
+    BreakAfterLossesSuggestion,
+    ResultsDegradeAfterLosses {
+        username: String,
+    },
+    EndSessionAfterLossesSuggestion,
+    ResultsDegradeInLongSessions {
+        username: String,
+    },
+    ShorterSessionsSuggestion,
+    GamesLostOnTimeAtHour {
+        username: String,
+        hour: u32,
+        timezone: String,
+        percentage: f32,
+    },
+    AvoidHourSuggestion,
+    TrendImproved {
+        username: String,
+        seconds: f32,
+    },
+    TrendWorsened {
+        username: String,
+        seconds: f32,
+    },
+    CorrespondenceTimeUsage {
+        username: String,
+        hours: f32,
+        percentage: f32,
+    },
+    CorrespondenceTimeouts {
+        username: String,
+        percentage: f32,
+    },
+    CorrespondenceTimeoutSuggestion,
+    InstantMoves {
+        username: String,
+        percentage: f32,
+    },
+    InstantMovesCostGames {
+        username: String,
+        win_rate: f32,
+        other_win_rate: f32,
+    },
+    InstantMovesSuggestion,
+    // Consistency score components
+    ConsistencyThinkTimeVariation {
+        percentage: f32,
+    },
+    ConsistencyTimeTrouble {
+        percentage: f32,
+    },
+    ConsistencyHalfTimeDeficit {
+        percentage: f32,
+    },
+    ConsistencyFlagLosses {
+        percentage: f32,
+    },
+    // Game warnings
+    GameHasNotEnoughMoves,
+    GameHasNoClocks,
+    GameHasMissingClocks,
+    GameWasAborted,
+    AnonymousOpponent,
+    ComputerOpponent,
+    CasualGame,
+    TournamentGame,
+    NotATournamentGame,
+    InternalErrorInGame,
+    // Errors
+    FetchFailed,
+    UserNotFound {
+        username: String,
+    },
+    RateLimited,
+    LichessUnavailable,
+    MalformedRequest {
+        details: String,
+    },
+    RequestedMoreGamesThanAvailable,
+    NotEnoughGamesToComputeAverage,
+    StreamResumptionFailed,
+    DatabaseFailure,
+    InternalFailure,
+    DataProcessingFailed,
+    UnknownTimezone {
+        timezone: String,
+    },
+    ReportRequiresPlayerAnalysis,
+    #[cfg(not(feature = "png"))] // Only raised without the png feature.
+    PngChartsDisabled,
+    #[cfg_attr(not(feature = "png"), allow(dead_code))] // Only raised by the png feature.
+    ChartRasterizationFailed {
+        details: String,
+    },
+
+    // Report and charts
+    ReportHeading {
+        username: String,
+    },
+    ReportGamesSummary {
+        games_count: i32,
+        perf_type: String,
+        user_color: String,
+    },
+    ReportAverageTime {
+        seconds: f32,
+    },
+    ReportTrendChartHeading,
+    ReportWinRateHeading,
+    ReportFlagsHeading,
+    ReportExcludedGamesHeading,
+    ReportFooter,
+    GameNumber {
+        game_number: i32,
+    },
+    GameResult {
+        win_status: String, // "win", "draw" or "loss"
+    },
+    ChartUserFlagged,
+    ChartOpponentsFlagged,
+    ChartGamesConsidered,
+    ChartUser,
+    ChartOpponent,
+    ChartMoveNumber {
+        move_number: usize,
+    },
+}
+
+// The requested color filter, "both" when the games of both colors are analysed.
+fn get_color_filter_name(user_color: &str, locale: Locale) -> &'static str {
+    match (user_color, locale) {
+        ("white", Locale::En) => "white",
+        ("black", Locale::En) => "black",
+        (_, Locale::En) => "both colors",
+        ("white", Locale::Fr) => "les blancs",
+        ("black", Locale::Fr) => "les noirs",
+        (_, Locale::Fr) => "les deux couleurs",
+        ("white", Locale::Es) => "blancas",
+        ("black", Locale::Es) => "negras",
+        (_, Locale::Es) => "ambos colores",
+    }
+}
+
+fn get_game_result_name(win_status: &str, locale: Locale) -> &'static str {
+    match (win_status, locale) {
+        ("win", Locale::En) => "win",
+        ("draw", Locale::En) => "draw",
+        (_, Locale::En) => "loss",
+        ("win", Locale::Fr) => "victoire",
+        ("draw", Locale::Fr) => "nulle",
+        (_, Locale::Fr) => "défaite",
+        ("win", Locale::Es) => "victoria",
+        ("draw", Locale::Es) => "tablas",
+        (_, Locale::Es) => "derrota",
+    }
+}
+
+impl Message {
+    pub fn localize(&self, locale: Locale) -> String {
+        let decimal = |value: &f32| format_decimal(*value, locale);
+        let percentage = |value: &f32| format!("{:.0}", value * 100.0);
+
+        match (self, locale) {
+            (Message::TimeNotComputed, Locale::En) => String::from(
+                "The time value was not computed. Check the errors panel for more information.",
+            ),
+            (Message::TimeNotComputed, Locale::Fr) => String::from(
+                "La valeur du temps n'a pas pu être calculée. Consultez le panneau des erreurs \
+                 pour plus d'informations.",
+            ),
+            (Message::TimeNotComputed, Locale::Es) => String::from(
+                "No se pudo calcular el valor del tiempo. Consulte el panel de errores para más \
+                 información.",
+            ),
+
+            (Message::AverageTimeBehind { username, seconds }, Locale::En) => format!(
+                "On average, {} is behind their opponents by {} seconds at half time in the \
+                 games.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::AverageTimeBehind { username, seconds }, Locale::Fr) => format!(
+                "En moyenne, {} a {} secondes de retard sur ses adversaires à la mi-partie.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::AverageTimeBehind { username, seconds }, Locale::Es) => format!(
+                "En promedio, {} va {} segundos por detrás de sus rivales a mitad de partida.",
+                username,
+                decimal(seconds)
+            ),
+
+            (Message::AverageTimeAhead { username, seconds }, Locale::En) => format!(
+                "On average, {} is ahead of their opponents by {} seconds at half time in the \
+                 games.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::AverageTimeAhead { username, seconds }, Locale::Fr) => format!(
+                "En moyenne, {} a {} secondes d'avance sur ses adversaires à la mi-partie.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::AverageTimeAhead { username, seconds }, Locale::Es) => format!(
+                "En promedio, {} va {} segundos por delante de sus rivales a mitad de partida.",
+                username,
+                decimal(seconds)
+            ),
+
+            (Message::AverageTimeEqual { username }, Locale::En) => format!(
+                "On average, {} is equal in time to their opponents at half time in the games.",
+                username
+            ),
+            (Message::AverageTimeEqual { username }, Locale::Fr) => format!(
+                "En moyenne, {} est à égalité de temps avec ses adversaires à la mi-partie.",
+                username
+            ),
+            (Message::AverageTimeEqual { username }, Locale::Es) => format!(
+                "En promedio, {} va igualado en tiempo con sus rivales a mitad de partida.",
+                username
+            ),
+
+            (Message::SteadyPaceSuggestion, Locale::En) => String::from(
+                "Keep long thinks for critical positions and play natural moves at a steady pace.",
+            ),
+            (Message::SteadyPaceSuggestion, Locale::Fr) => String::from(
+                "Réservez les longues réflexions aux positions critiques et jouez les coups \
+                 naturels à un rythme régulier.",
+            ),
+            (Message::SteadyPaceSuggestion, Locale::Es) => String::from(
+                "Reserve las reflexiones largas para las posiciones críticas y juegue las \
+                 jugadas naturales a un ritmo constante.",
+            ),
+
+            (
+                Message::GamesLostOnTime {
+                    username,
+                    percentage: rate,
+                },
+                Locale::En,
+            ) => format!(
+                "{} lost {}% of the games on time.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::GamesLostOnTime {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "{} a perdu {} % des parties au temps.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::GamesLostOnTime {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "{} perdió el {} % de las partidas por tiempo.",
+                username,
+                percentage(rate)
+            ),
+
+            (Message::TimeTroubleSuggestion, Locale::En) => String::from(
+                "When under a minute, switch to safe and quick moves instead of looking for the \
+                 best one.",
+            ),
+            (Message::TimeTroubleSuggestion, Locale::Fr) => String::from(
+                "Sous la minute, privilégiez des coups sûrs et rapides plutôt que de chercher le \
+                 meilleur.",
+            ),
+            (Message::TimeTroubleSuggestion, Locale::Es) => String::from(
+                "Con menos de un minuto, juegue jugadas seguras y rápidas en lugar de buscar la \
+                 mejor.",
+            ),
+
+            (
+                Message::OpeningTimeDeficit {
+                    username,
+                    moves_count,
+                    seconds,
+                },
+                Locale::En,
+            ) => format!(
+                "After {} moves, {} is already {} seconds behind their opponents.",
+                moves_count,
+                username,
+                decimal(seconds)
+            ),
+            (
+                Message::OpeningTimeDeficit {
+                    username,
+                    moves_count,
+                    seconds,
+                },
+                Locale::Fr,
+            ) => format!(
+                "Après {} coups, {} a déjà {} secondes de retard sur ses adversaires.",
+                moves_count,
+                username,
+                decimal(seconds)
+            ),
+            (
+                Message::OpeningTimeDeficit {
+                    username,
+                    moves_count,
+                    seconds,
+                },
+                Locale::Es,
+            ) => format!(
+                "Tras {} jugadas, {} ya va {} segundos por detrás de sus rivales.",
+                moves_count,
+                username,
+                decimal(seconds)
+            ),
+
+            (Message::OpeningPreparationSuggestion, Locale::En) => String::from(
+                "Review the openings you play so that the first moves come without thinking.",
+            ),
+            (Message::OpeningPreparationSuggestion, Locale::Fr) => {
+                String::from("Révisez vos ouvertures pour jouer les premiers coups sans réfléchir.")
+            }
+            (Message::OpeningPreparationSuggestion, Locale::Es) => {
+                String::from("Repase sus aperturas para jugar las primeras jugadas sin pensar.")
+            }
+
+            (Message::HigherRatedOpponentsSlowdown { username, seconds }, Locale::En) => format!(
+                "Against higher rated opponents, {} is {} seconds slower at half time than in \
+                 their other games.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::HigherRatedOpponentsSlowdown { username, seconds }, Locale::Fr) => format!(
+                "Face à des adversaires mieux classés, {} est {} secondes plus lent à la \
+                 mi-partie que dans ses autres parties.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::HigherRatedOpponentsSlowdown { username, seconds }, Locale::Es) => format!(
+                "Contra rivales de mayor puntuación, {} es {} segundos más lento a mitad de \
+                 partida que en sus demás partidas.",
+                username,
+                decimal(seconds)
+            ),
+
+            (Message::HigherRatedOpponentsSuggestion, Locale::En) => String::from(
+                "Trust your preparation against stronger players: they also make mistakes under \
+                 time pressure.",
+            ),
+            (Message::HigherRatedOpponentsSuggestion, Locale::Fr) => String::from(
+                "Faites confiance à votre préparation face aux joueurs plus forts : eux aussi se \
+                 trompent sous la pression du temps.",
+            ),
+            (Message::HigherRatedOpponentsSuggestion, Locale::Es) => String::from(
+                "Confíe en su preparación contra jugadores más fuertes: ellos también se \
+                 equivocan bajo la presión del tiempo.",
+            ),
+
+            (Message::SpeedUpAfterLosses { username }, Locale::En) => format!(
+                "{} plays noticeably faster after consecutive losses.",
+                username
+            ),
+            (Message::SpeedUpAfterLosses { username }, Locale::Fr) => format!(
+                "{} joue nettement plus vite après des défaites consécutives.",
+                username
+            ),
+            (Message::SpeedUpAfterLosses { username }, Locale::Es) => format!(
+                "{} juega notablemente más rápido tras derrotas consecutivas.",
+                username
+            ),
+
+            (Message::BreakAfterLossesSuggestion, Locale::En) => String::from(
+                "After two losses in a row, take a short break before starting a new game.",
+            ),
+            (Message::BreakAfterLossesSuggestion, Locale::Fr) => String::from(
+                "Après deux défaites d'affilée, faites une courte pause avant de relancer une \
+                 partie.",
+            ),
+            (Message::BreakAfterLossesSuggestion, Locale::Es) => String::from(
+                "Tras dos derrotas seguidas, tómese un breve descanso antes de empezar otra \
+                 partida.",
+            ),
+
+            (Message::ResultsDegradeAfterLosses { username }, Locale::En) => format!(
+                "{} wins less often in the games following consecutive losses.",
+                username
+            ),
+            (Message::ResultsDegradeAfterLosses { username }, Locale::Fr) => format!(
+                "{} gagne moins souvent les parties qui suivent des défaites consécutives.",
+                username
+            ),
+            (Message::ResultsDegradeAfterLosses { username }, Locale::Es) => format!(
+                "{} gana con menos frecuencia las partidas que siguen a derrotas consecutivas.",
+                username
+            ),
+
+            (Message::EndSessionAfterLossesSuggestion, Locale::En) => {
+                String::from("Consider ending the session after a losing streak.")
+            }
+            (Message::EndSessionAfterLossesSuggestion, Locale::Fr) => {
+                String::from("Envisagez d'arrêter la session après une série de défaites.")
+            }
+            (Message::EndSessionAfterLossesSuggestion, Locale::Es) => {
+                String::from("Considere terminar la sesión tras una racha de derrotas.")
+            }
+
+            (Message::ResultsDegradeInLongSessions { username }, Locale::En) => {
+                format!("{} wins less often late in long sessions.", username)
+            }
+            (Message::ResultsDegradeInLongSessions { username }, Locale::Fr) => {
+                format!("{} gagne moins souvent en fin de longue session.", username)
+            }
+            (Message::ResultsDegradeInLongSessions { username }, Locale::Es) => format!(
+                "{} gana con menos frecuencia al final de las sesiones largas.",
+                username
+            ),
+
+            (Message::ShorterSessionsSuggestion, Locale::En) => {
+                String::from("Play shorter sessions to keep your focus.")
+            }
+            (Message::ShorterSessionsSuggestion, Locale::Fr) => {
+                String::from("Jouez des sessions plus courtes pour rester concentré.")
+            }
+            (Message::ShorterSessionsSuggestion, Locale::Es) => {
+                String::from("Juegue sesiones más cortas para mantener la concentración.")
+            }
+
+            (
+                Message::GamesLostOnTimeAtHour {
+                    username,
+                    hour,
+                    timezone,
+                    percentage: rate,
+                },
+                Locale::En,
+            ) => format!(
+                "Between {:02}:00 and {:02}:59 ({}), {} loses {}% of the games on time.",
+                hour,
+                hour,
+                timezone,
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::GamesLostOnTimeAtHour {
+                    username,
+                    hour,
+                    timezone,
+                    percentage: rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "Entre {:02}h00 et {:02}h59 ({}), {} perd {} % des parties au temps.",
+                hour,
+                hour,
+                timezone,
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::GamesLostOnTimeAtHour {
+                    username,
+                    hour,
+                    timezone,
+                    percentage: rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "Entre las {:02}:00 y las {:02}:59 ({}), {} pierde el {} % de las partidas por \
+                 tiempo.",
+                hour,
+                hour,
+                timezone,
+                username,
+                percentage(rate)
+            ),
+
+            (Message::AvoidHourSuggestion, Locale::En) => String::from(
+                "Avoid playing at this time of the day, or pick a longer time control.",
+            ),
+            (Message::AvoidHourSuggestion, Locale::Fr) => String::from(
+                "Évitez de jouer à cette heure de la journée, ou choisissez une cadence plus \
+                 longue.",
+            ),
+            (Message::AvoidHourSuggestion, Locale::Es) => String::from(
+                "Evite jugar a esta hora del día, o elija un control de tiempo más largo.",
+            ),
+
+            (Message::TrendImproved { username, seconds }, Locale::En) => format!(
+                "The half time differential of {} improved by {} seconds over the analysed games.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::TrendImproved { username, seconds }, Locale::Fr) => format!(
+                "Le différentiel de temps à la mi-partie de {} s'est amélioré de {} secondes sur \
+                 les parties analysées.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::TrendImproved { username, seconds }, Locale::Es) => format!(
+                "El diferencial de tiempo a mitad de partida de {} mejoró {} segundos en las \
+                 partidas analizadas.",
+                username,
+                decimal(seconds)
+            ),
+
+            (Message::TrendWorsened { username, seconds }, Locale::En) => format!(
+                "The half time differential of {} worsened by {} seconds over the analysed games.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::TrendWorsened { username, seconds }, Locale::Fr) => format!(
+                "Le différentiel de temps à la mi-partie de {} s'est dégradé de {} secondes sur \
+                 les parties analysées.",
+                username,
+                decimal(seconds)
+            ),
+            (Message::TrendWorsened { username, seconds }, Locale::Es) => format!(
+                "El diferencial de tiempo a mitad de partida de {} empeoró {} segundos en las \
+                 partidas analizadas.",
+                username,
+                decimal(seconds)
+            ),
+
+            (
+                Message::CorrespondenceTimeUsage {
+                    username,
+                    hours,
+                    percentage: rate,
+                },
+                Locale::En,
+            ) => format!(
+                "In the correspondence games of {}, a move takes {} hours on average, {}% of the \
+                 time allowed per move.",
+                username,
+                decimal(hours),
+                percentage(rate)
+            ),
+            (
+                Message::CorrespondenceTimeUsage {
+                    username,
+                    hours,
+                    percentage: rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "Dans les parties par correspondance de {}, un coup prend {} heures en moyenne, \
+                 soit {} % du temps accordé par coup.",
+                username,
+                decimal(hours),
+                percentage(rate)
+            ),
+            (
+                Message::CorrespondenceTimeUsage {
+                    username,
+                    hours,
+                    percentage: rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "En las partidas por correspondencia de {}, una jugada tarda {} horas en \
+                 promedio, el {} % del tiempo permitido por jugada.",
+                username,
+                decimal(hours),
+                percentage(rate)
+            ),
+
+            (
+                Message::CorrespondenceTimeouts {
+                    username,
+                    percentage: rate,
+                },
+                Locale::En,
+            ) => format!(
+                "{} ran out of time in {}% of the correspondence games.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::CorrespondenceTimeouts {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "{} a dépassé le temps imparti dans {} % des parties par correspondance.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::CorrespondenceTimeouts {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "{} se quedó sin tiempo en el {} % de las partidas por correspondencia.",
+                username,
+                percentage(rate)
+            ),
+
+            (Message::CorrespondenceTimeoutSuggestion, Locale::En) => String::from(
+                "Turn on move notifications, and pick more days per move for the busy weeks.",
+            ),
+            (Message::CorrespondenceTimeoutSuggestion, Locale::Fr) => String::from(
+                "Activez les notifications de coups, et choisissez plus de jours par coup pour \
+                 les semaines chargées.",
+            ),
+            (Message::CorrespondenceTimeoutSuggestion, Locale::Es) => String::from(
+                "Active las notificaciones de jugadas, y elija más días por jugada para las \
+                 semanas ocupadas.",
+            ),
+
+            (
+                Message::InstantMoves {
+                    username,
+                    percentage: rate,
+                },
+                Locale::En,
+            ) => format!(
+                "{} plays {}% of their moves instantly, premoves included.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::InstantMoves {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "{} joue {} % de ses coups instantanément, prémouvements compris.",
+                username,
+                percentage(rate)
+            ),
+            (
+                Message::InstantMoves {
+                    username,
+                    percentage: rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "{} juega el {} % de sus jugadas al instante, premovimientos incluidos.",
+                username,
+                percentage(rate)
+            ),
+
+            (
+                Message::InstantMovesCostGames {
+                    username,
+                    win_rate,
+                    other_win_rate,
+                },
+                Locale::En,
+            ) => format!(
+                "{} wins {}% of the games in which they move instantly the most, against {}% \
+                 of the other games.",
+                username,
+                percentage(win_rate),
+                percentage(other_win_rate)
+            ),
+            (
+                Message::InstantMovesCostGames {
+                    username,
+                    win_rate,
+                    other_win_rate,
+                },
+                Locale::Fr,
+            ) => format!(
+                "{} gagne {} % des parties où ses coups sont le plus souvent instantanés, \
+                 contre {} % des autres parties.",
+                username,
+                percentage(win_rate),
+                percentage(other_win_rate)
+            ),
+            (
+                Message::InstantMovesCostGames {
+                    username,
+                    win_rate,
+                    other_win_rate,
+                },
+                Locale::Es,
+            ) => format!(
+                "{} gana el {} % de las partidas en las que más juega al instante, frente al \
+                 {} % de las demás partidas.",
+                username,
+                percentage(win_rate),
+                percentage(other_win_rate)
+            ),
+
+            (Message::InstantMovesSuggestion, Locale::En) => String::from(
+                "Keep premoves for forced replies, and take a second on the moves that change \
+                 the position.",
+            ),
+            (Message::InstantMovesSuggestion, Locale::Fr) => String::from(
+                "Gardez les prémouvements pour les réponses forcées, et prenez une seconde sur \
+                 les coups qui changent la position.",
+            ),
+            (Message::InstantMovesSuggestion, Locale::Es) => String::from(
+                "Reserve los premovimientos para las respuestas forzadas, y tómese un segundo en \
+                 las jugadas que cambian la posición.",
+            ),
+
+            (Message::ConsistencyThinkTimeVariation { percentage: rate }, Locale::En) => {
+                format!(
+                    "Within a game, the time spent on a move deviates by {}% from the average \
+                     move.",
+                    percentage(rate)
+                )
+            }
+            (Message::ConsistencyThinkTimeVariation { percentage: rate }, Locale::Fr) => {
+                format!(
+                    "Au sein d'une partie, le temps passé sur un coup s'écarte de {} % du coup \
+                     moyen.",
+                    percentage(rate)
+                )
+            }
+            (Message::ConsistencyThinkTimeVariation { percentage: rate }, Locale::Es) => {
+                format!(
+                    "Dentro de una partida, el tiempo dedicado a una jugada se desvía un {} % de \
+                     la jugada media.",
+                    percentage(rate)
+                )
+            }
+
+            (Message::ConsistencyTimeTrouble { percentage: rate }, Locale::En) => format!(
+                "{}% of the games went below 10% of the starting clock.",
+                percentage(rate)
+            ),
+            (Message::ConsistencyTimeTrouble { percentage: rate }, Locale::Fr) => format!(
+                "{} % des parties sont passées sous 10 % du temps initial.",
+                percentage(rate)
+            ),
+            (Message::ConsistencyTimeTrouble { percentage: rate }, Locale::Es) => format!(
+                "El {} % de las partidas bajó del 10 % del tiempo inicial.",
+                percentage(rate)
+            ),
+
+            (Message::ConsistencyHalfTimeDeficit { percentage: rate }, Locale::En) => format!(
+                "{}% of the games were behind on the clock at half time.",
+                percentage(rate)
+            ),
+            (Message::ConsistencyHalfTimeDeficit { percentage: rate }, Locale::Fr) => format!(
+                "{} % des parties étaient en retard à la pendule à mi-partie.",
+                percentage(rate)
+            ),
+            (Message::ConsistencyHalfTimeDeficit { percentage: rate }, Locale::Es) => format!(
+                "El {} % de las partidas iba por detrás en el reloj a mitad de partida.",
+                percentage(rate)
+            ),
+
+            (Message::ConsistencyFlagLosses { percentage: rate }, Locale::En) => {
+                format!("{}% of the games were lost on time.", percentage(rate))
+            }
+            (Message::ConsistencyFlagLosses { percentage: rate }, Locale::Fr) => {
+                format!(
+                    "{} % des parties ont été perdues au temps.",
+                    percentage(rate)
+                )
+            }
+            (Message::ConsistencyFlagLosses { percentage: rate }, Locale::Es) => {
+                format!(
+                    "El {} % de las partidas se perdió por tiempo.",
+                    percentage(rate)
+                )
+            }
+
+            (Message::GameHasNotEnoughMoves, Locale::En) => {
+                String::from("Game does not have enough moves.")
+            }
+            (Message::GameHasNotEnoughMoves, Locale::Fr) => {
+                String::from("La partie ne comporte pas assez de coups.")
+            }
+            (Message::GameHasNotEnoughMoves, Locale::Es) => {
+                String::from("La partida no tiene suficientes jugadas.")
+            }
+
+            (Message::GameHasNoClocks, Locale::En) => String::from(
+                "Game has no clock data, it is only used for the correspondence analysis.",
+            ),
+            (Message::GameHasNoClocks, Locale::Fr) => String::from(
+                "La partie ne comporte pas de données de pendule, elle ne sert qu'à l'analyse des \
+                 parties par correspondance.",
+            ),
+            (Message::GameHasNoClocks, Locale::Es) => String::from(
+                "La partida no tiene datos de reloj, solo se usa en el análisis de las partidas \
+                 por correspondencia.",
+            ),
+
+            (Message::GameHasMissingClocks, Locale::En) => {
+                String::from("Game clocks were not recorded for every move.")
+            }
+            (Message::GameHasMissingClocks, Locale::Fr) => {
+                String::from("La pendule n'a pas été enregistrée pour chaque coup de la partie.")
+            }
+            (Message::GameHasMissingClocks, Locale::Es) => {
+                String::from("El reloj no se registró en todas las jugadas de la partida.")
+            }
+
+            (Message::GameWasAborted, Locale::En) => String::from("Game was aborted."),
+            (Message::GameWasAborted, Locale::Fr) => String::from("La partie a été annulée."),
+            (Message::GameWasAborted, Locale::Es) => String::from("La partida fue anulada."),
+
+            (Message::AnonymousOpponent, Locale::En) => {
+                String::from("Game was played against an anonymous opponent.")
+            }
+            (Message::AnonymousOpponent, Locale::Fr) => {
+                String::from("La partie a été jouée contre un adversaire anonyme.")
+            }
+            (Message::AnonymousOpponent, Locale::Es) => {
+                String::from("La partida se jugó contra un rival anónimo.")
+            }
+
+            (Message::ComputerOpponent, Locale::En) => {
+                String::from("Game was played against the computer.")
+            }
+            (Message::ComputerOpponent, Locale::Fr) => {
+                String::from("La partie a été jouée contre l'ordinateur.")
+            }
+            (Message::ComputerOpponent, Locale::Es) => {
+                String::from("La partida se jugó contra la computadora.")
+            }
+
+            (Message::CasualGame, Locale::En) => {
+                String::from("Game was casual, only rated games against players are analysed.")
+            }
+            (Message::CasualGame, Locale::Fr) => String::from(
+                "La partie n'était pas classée, seules les parties classées contre des joueurs \
+                 sont analysées.",
+            ),
+            (Message::CasualGame, Locale::Es) => String::from(
+                "La partida no era puntuada, solo se analizan las partidas puntuadas contra \
+                 jugadores.",
+            ),
+
+            (Message::TournamentGame, Locale::En) => {
+                String::from("Game was played in a tournament.")
+            }
+            (Message::TournamentGame, Locale::Fr) => {
+                String::from("La partie a été jouée dans un tournoi.")
+            }
+            (Message::TournamentGame, Locale::Es) => {
+                String::from("La partida se jugó en un torneo.")
+            }
+
+            (Message::NotATournamentGame, Locale::En) => {
+                String::from("Game was not played in a tournament.")
+            }
+            (Message::NotATournamentGame, Locale::Fr) => {
+                String::from("La partie n'a pas été jouée dans un tournoi.")
+            }
+            (Message::NotATournamentGame, Locale::Es) => {
+                String::from("La partida no se jugó en un torneo.")
+            }
+
+            (Message::InternalErrorInGame, Locale::En) => {
+                String::from("An internal error occured while processing this game.")
+            }
+            (Message::InternalErrorInGame, Locale::Fr) => {
+                String::from("Une erreur interne est survenue lors du traitement de cette partie.")
+            }
+            (Message::InternalErrorInGame, Locale::Es) => {
+                String::from("Se produjo un error interno al procesar esta partida.")
+            }
+
+            (Message::FetchFailed, Locale::En) => String::from(
+                "There was a problem fetching the data. Please check your internet connection.",
+            ),
+            (Message::FetchFailed, Locale::Fr) => String::from(
+                "Un problème est survenu lors de la récupération des données. Veuillez vérifier \
+                 votre connexion internet.",
+            ),
+            (Message::FetchFailed, Locale::Es) => String::from(
+                "Hubo un problema al obtener los datos. Compruebe su conexión a internet.",
+            ),
+
+            (Message::UserNotFound { username }, Locale::En) => {
+                format!("No lichess user named {} was found.", username)
+            }
+            (Message::UserNotFound { username }, Locale::Fr) => {
+                format!(
+                    "Aucun utilisateur lichess nommé {} n'a été trouvé.",
+                    username
+                )
+            }
+            (Message::UserNotFound { username }, Locale::Es) => {
+                format!(
+                    "No se encontró ningún usuario de lichess llamado {}.",
+                    username
+                )
+            }
+
+            (Message::RateLimited, Locale::En) => String::from(
+                "Lichess is receiving too many requests. Please wait a minute and try again.",
+            ),
+            (Message::RateLimited, Locale::Fr) => String::from(
+                "Lichess reçoit trop de requêtes. Veuillez patienter une minute puis réessayer.",
+            ),
+            (Message::RateLimited, Locale::Es) => String::from(
+                "Lichess está recibiendo demasiadas solicitudes. Espere un minuto e inténtelo de \
+                 nuevo.",
+            ),
+
+            (Message::LichessUnavailable, Locale::En) => {
+                String::from("Lichess could not process the request. Please try again later.")
+            }
+            (Message::LichessUnavailable, Locale::Fr) => {
+                String::from("Lichess n'a pas pu traiter la requête. Veuillez réessayer plus tard.")
+            }
+            (Message::LichessUnavailable, Locale::Es) => {
+                String::from("Lichess no pudo procesar la solicitud. Inténtelo de nuevo más tarde.")
+            }
+
+            (Message::MalformedRequest { details }, Locale::En) => {
+                format!("The request is malformed: {}", details)
+            }
+            (Message::MalformedRequest { details }, Locale::Fr) => {
+                format!("La requête est mal formée : {}", details)
+            }
+            (Message::MalformedRequest { details }, Locale::Es) => {
+                format!("La solicitud está mal formada: {}", details)
+            }
+
+            (Message::RequestedMoreGamesThanAvailable, Locale::En) => {
+                String::from("The user has no games matching the requested game mode and color.")
+            }
+            (Message::RequestedMoreGamesThanAvailable, Locale::Fr) => String::from(
+                "L'utilisateur n'a aucune partie correspondant au mode de jeu et à la couleur \
+                 demandés.",
+            ),
+            (Message::RequestedMoreGamesThanAvailable, Locale::Es) => String::from(
+                "El usuario no tiene partidas que coincidan con el modo de juego y el color \
+                 solicitados.",
+            ),
+
+            (Message::NotEnoughGamesToComputeAverage, Locale::En) => String::from(
+                "None of the fetched games could be analysed. Try requesting more games.",
+            ),
+            (Message::NotEnoughGamesToComputeAverage, Locale::Fr) => String::from(
+                "Aucune des parties récupérées n'a pu être analysée. Essayez de demander plus de \
+                 parties.",
+            ),
+            (Message::NotEnoughGamesToComputeAverage, Locale::Es) => String::from(
+                "No se pudo analizar ninguna de las partidas obtenidas. Intente solicitar más \
+                 partidas.",
+            ),
+
+            (Message::StreamResumptionFailed, Locale::En) => String::from(
+                "The connection to lichess dropped before any game was received and the download \
+                 could not be resumed. Please try again.",
+            ),
+            (Message::StreamResumptionFailed, Locale::Fr) => String::from(
+                "La connexion à lichess a été interrompue avant la réception de la première partie \
+                 et le téléchargement n'a pas pu reprendre. Veuillez réessayer.",
+            ),
+            (Message::StreamResumptionFailed, Locale::Es) => String::from(
+                "La conexión con lichess se interrumpió antes de recibir la primera partida y la \
+                 descarga no pudo reanudarse. Inténtelo de nuevo.",
+            ),
+
+            (Message::DatabaseFailure, Locale::En) => {
+                String::from("The request statistics could not be saved. Please try again later.")
+            }
+            (Message::DatabaseFailure, Locale::Fr) => String::from(
+                "Les statistiques de la requête n'ont pas pu être enregistrées. Veuillez \
+                 réessayer plus tard.",
+            ),
+            (Message::DatabaseFailure, Locale::Es) => String::from(
+                "No se pudieron guardar las estadísticas de la solicitud. Inténtelo de nuevo más \
+                 tarde.",
+            ),
+
+            (Message::InternalFailure, Locale::En) => String::from(
+                "There was an internal problem with the server. Please try again later.",
+            ),
+            (Message::InternalFailure, Locale::Fr) => String::from(
+                "Le serveur a rencontré un problème interne. Veuillez réessayer plus tard.",
+            ),
+            (Message::InternalFailure, Locale::Es) => String::from(
+                "Hubo un problema interno en el servidor. Inténtelo de nuevo más tarde.",
+            ),
+
+            (Message::DataProcessingFailed, Locale::En) => {
+                String::from("There was a problem processing the data.")
+            }
+            (Message::DataProcessingFailed, Locale::Fr) => {
+                String::from("Un problème est survenu lors du traitement des données.")
+            }
+            (Message::DataProcessingFailed, Locale::Es) => {
+                String::from("Hubo un problema al procesar los datos.")
+            }
+
+            (Message::UnknownTimezone { timezone }, Locale::En) => {
+                format!("Unknown timezone: {}", timezone)
+            }
+            (Message::UnknownTimezone { timezone }, Locale::Fr) => {
+                format!("Fuseau horaire inconnu : {}", timezone)
+            }
+            (Message::UnknownTimezone { timezone }, Locale::Es) => {
+                format!("Zona horaria desconocida: {}", timezone)
+            }
+
+            (Message::ReportRequiresPlayerAnalysis, Locale::En) => {
+                String::from("Reports can only be generated for player analyses.")
+            }
+            (Message::ReportRequiresPlayerAnalysis, Locale::Fr) => String::from(
+                "Les rapports ne peuvent être générés que pour des analyses de joueur.",
+            ),
+            (Message::ReportRequiresPlayerAnalysis, Locale::Es) => {
+                String::from("Los informes solo se pueden generar para análisis de jugadores.")
+            }
+
+            #[cfg(not(feature = "png"))]
+            (Message::PngChartsDisabled, Locale::En) => {
+                String::from("PNG charts are not enabled on this server, request an SVG chart.")
+            }
+            #[cfg(not(feature = "png"))]
+            (Message::PngChartsDisabled, Locale::Fr) => String::from(
+                "Les graphiques PNG ne sont pas activés sur ce serveur, demandez un graphique SVG.",
+            ),
+            #[cfg(not(feature = "png"))]
+            (Message::PngChartsDisabled, Locale::Es) => String::from(
+                "Los gráficos PNG no están habilitados en este servidor, solicite un gráfico SVG.",
+            ),
+
+            (Message::ChartRasterizationFailed { details }, Locale::En) => {
+                format!("The chart could not be rendered: {}", details)
+            }
+            (Message::ChartRasterizationFailed { details }, Locale::Fr) => {
+                format!("Le graphique n'a pas pu être rendu : {}", details)
+            }
+            (Message::ChartRasterizationFailed { details }, Locale::Es) => {
+                format!("No se pudo generar el gráfico: {}", details)
+            }
+
+            (Message::ReportHeading { username }, Locale::En) => {
+                format!("Time management report for {}", username)
+            }
+            (Message::ReportHeading { username }, Locale::Fr) => {
+                format!("Rapport de gestion du temps de {}", username)
+            }
+            (Message::ReportHeading { username }, Locale::Es) => {
+                format!("Informe de gestión del tiempo de {}", username)
+            }
+
+            (
+                Message::ReportGamesSummary {
+                    games_count,
+                    perf_type,
+                    user_color,
+                },
+                Locale::En,
+            ) => format!(
+                "{} {} games as {}.",
+                games_count,
+                perf_type,
+                get_color_filter_name(user_color, locale)
+            ),
+            (
+                Message::ReportGamesSummary {
+                    games_count,
+                    perf_type,
+                    user_color,
+                },
+                Locale::Fr,
+            ) => format!(
+                "{} parties de {} avec {}.",
+                games_count,
+                perf_type,
+                get_color_filter_name(user_color, locale)
+            ),
+            (
+                Message::ReportGamesSummary {
+                    games_count,
+                    perf_type,
+                    user_color,
+                },
+                Locale::Es,
+            ) => format!(
+                "{} partidas de {} con {}.",
+                games_count,
+                perf_type,
+                get_color_filter_name(user_color, locale)
+            ),
+
+            (Message::ReportAverageTime { seconds }, Locale::En) => format!(
+                "Average half time differential: {} seconds.",
+                decimal(seconds)
+            ),
+            (Message::ReportAverageTime { seconds }, Locale::Fr) => format!(
+                "Différentiel moyen à la mi-partie : {} secondes.",
+                decimal(seconds)
+            ),
+            (Message::ReportAverageTime { seconds }, Locale::Es) => format!(
+                "Diferencial medio a mitad de partida: {} segundos.",
+                decimal(seconds)
+            ),
+
+            (Message::ReportTrendChartHeading, Locale::En) => {
+                String::from("Half time differential per game")
+            }
+            (Message::ReportTrendChartHeading, Locale::Fr) => {
+                String::from("Différentiel à la mi-partie par partie")
+            }
+            (Message::ReportTrendChartHeading, Locale::Es) => {
+                String::from("Diferencial a mitad de partida por partida")
+            }
+
+            (Message::ReportWinRateHeading, Locale::En) => String::from("Win rate"),
+            (Message::ReportWinRateHeading, Locale::Fr) => String::from("Taux de victoire"),
+            (Message::ReportWinRateHeading, Locale::Es) => String::from("Tasa de victorias"),
+
+            (Message::ReportFlagsHeading, Locale::En) => String::from("Flags"),
+            (Message::ReportFlagsHeading, Locale::Fr) => String::from("Drapeaux tombés"),
+            (Message::ReportFlagsHeading, Locale::Es) => String::from("Caídas de bandera"),
+
+            (Message::ReportExcludedGamesHeading, Locale::En) => {
+                String::from("Games excluded from the analysis")
+            }
+            (Message::ReportExcludedGamesHeading, Locale::Fr) => {
+                String::from("Parties exclues de l'analyse")
+            }
+            (Message::ReportExcludedGamesHeading, Locale::Es) => {
+                String::from("Partidas excluidas del análisis")
+            }
+
+            (Message::ReportFooter, Locale::En) => {
+                String::from("Generated by Chess Pace Tracker from lichess.org data.")
+            }
+            (Message::ReportFooter, Locale::Fr) => {
+                String::from("Généré par Chess Pace Tracker à partir des données de lichess.org.")
+            }
+            (Message::ReportFooter, Locale::Es) => {
+                String::from("Generado por Chess Pace Tracker con datos de lichess.org.")
+            }
+
+            (Message::GameNumber { game_number }, Locale::En) => format!("Game {}", game_number),
+            (Message::GameNumber { game_number }, Locale::Fr) => format!("Partie {}", game_number),
+            (Message::GameNumber { game_number }, Locale::Es) => {
+                format!("Partida {}", game_number)
+            }
+
+            (Message::GameResult { win_status }, _) => {
+                String::from(get_game_result_name(win_status, locale))
+            }
+
+            (Message::ChartUserFlagged, Locale::En) => String::from("User flagged"),
+            (Message::ChartUserFlagged, Locale::Fr) => String::from("Victoires au temps"),
+            (Message::ChartUserFlagged, Locale::Es) => String::from("Victorias por tiempo"),
+
+            (Message::ChartOpponentsFlagged, Locale::En) => String::from("Opponents flagged"),
+            (Message::ChartOpponentsFlagged, Locale::Fr) => String::from("Défaites au temps"),
+            (Message::ChartOpponentsFlagged, Locale::Es) => String::from("Derrotas por tiempo"),
+
+            (Message::ChartGamesConsidered, Locale::En) => String::from("Games considered"),
+            (Message::ChartGamesConsidered, Locale::Fr) => String::from("Parties analysées"),
+            (Message::ChartGamesConsidered, Locale::Es) => String::from("Partidas analizadas"),
+
+            (Message::ChartUser, Locale::En) => String::from("User"),
+            (Message::ChartUser, Locale::Fr) => String::from("Joueur"),
+            (Message::ChartUser, Locale::Es) => String::from("Jugador"),
+
+            (Message::ChartOpponent, Locale::En) => String::from("Opponent"),
+            (Message::ChartOpponent, Locale::Fr) => String::from("Adversaire"),
+            (Message::ChartOpponent, Locale::Es) => String::from("Rival"),
+
+            (Message::ChartMoveNumber { move_number }, Locale::En) => {
+                format!("move {}", move_number)
+            }
+            (Message::ChartMoveNumber { move_number }, Locale::Fr) => {
+                format!("coup {}", move_number)
+            }
+            (Message::ChartMoveNumber { move_number }, Locale::Es) => {
+                format!("jugada {}", move_number)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CA,fr;q=0.9,en;q=0.8"),
+            Some(Locale::Fr)
+        );
+        assert_eq!(
+            parse_accept_language("de-DE, es;q=0.5, en;q=0.7"),
+            Some(Locale::En)
+        );
+        assert_eq!(parse_accept_language("es;q=0, pt"), None);
+        assert_eq!(parse_accept_language(""), None);
+    }
+
+    #[test]
+    fn test_localize() {
+        let message = Message::AverageTimeBehind {
+            username: String::from("user"),
+            seconds: 1.5,
+        };
+        assert_eq!(
+            message.localize(Locale::Fr),
+            "En moyenne, user a 1,50 secondes de retard sur ses adversaires à la mi-partie."
+        );
+        assert_eq!(
+            message.localize(Locale::En),
+            "On average, user is behind their opponents by 1.50 seconds at half time in the games."
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::games_info_generator::GameInfo;
+use crate::lichess_client::LICHESS_BASE_URL;
+use crate::premove_generator::{self, TimedMoveDuration};
+use crate::service_intermediary::GameFetchWarning;
+use crate::util;
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Maximum number of long thinks listed per game, for each of the absolute and relative rankings.
+const CRITICAL_MOMENTS_PER_GAME: usize = 3;
+
+// A move is a long think when it takes at least this many times the average move of the user
+// in the game.
+const LONG_THINK_AVERAGE_RATIO: f32 = 3.0;
+
+// Width of the move number ranges of the long thinks distribution.
+const MOVES_PER_RANGE: usize = 10;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LongThink {
+    pub ply: usize,
+    pub move_number: usize,
+    pub san: String,
+    pub clock_before_move: f32, // seconds
+    pub clock_after_move: f32,  // seconds, increment included
+    pub think_time: f32,        // seconds
+    pub clock_share: f32,       // Share of the clock before the move spent on it
+    pub analysis_url: String,   // Lichess analysis board, on the position after the move
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct GameCriticalMoments {
+    pub game_number: usize,
+    pub longest_thinks: Vec<LongThink>,       // By think time
+    pub largest_clock_shares: Vec<LongThink>, // By share of the clock spent
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LongThinkMoveRange {
+    pub first_move: usize,
+    pub last_move: usize,
+    pub long_thinks_count: usize,
+    pub long_thinks_rate: f32, // Share of all the long thinks
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LongThinksAnalysis {
+    pub critical_moments: Vec<GameCriticalMoments>,
+    pub long_thinks_per_move_range: Vec<LongThinkMoveRange>,
+}
+
+// The ply anchor opens the board on the position reached by the move, from the user side.
+pub fn get_analysis_url(game_info: &GameInfo, ply: usize) -> String {
+    format!(
+        "{}/{}/{}#{}",
+        LICHESS_BASE_URL, game_info.game_id, game_info.user_color, ply
+    )
+}
+
+fn get_long_think(game_info: &GameInfo, move_duration: &TimedMoveDuration) -> LongThink {
+    LongThink {
+        ply: move_duration.ply,
+        move_number: move_duration.move_number,
+        san: game_info.timed_moves[move_duration.ply - 1]
+            .move_key
+            .clone(),
+        clock_before_move: util::convert_centiseconds_to_seconds(
+            move_duration.clock_before_move as i32,
+        ),
+        clock_after_move: util::convert_centiseconds_to_seconds(
+            move_duration.clock_after_move as i32,
+        ),
+        think_time: util::convert_centiseconds_to_seconds(move_duration.duration as i32),
+        clock_share: get_clock_share(move_duration),
+        analysis_url: get_analysis_url(game_info, move_duration.ply),
+    }
+}
+
+fn get_clock_share(move_duration: &TimedMoveDuration) -> f32 {
+    if move_duration.clock_before_move <= 0 {
+        return 1.0;
+    }
+    (move_duration.duration as f32 / move_duration.clock_before_move as f32).min(1.0)
+}
+
+// Ties are ranked by ply so that the list does not depend on the sort stability.
+fn get_top_moves<'a>(
+    long_thinks: &[&'a TimedMoveDuration],
+    key: impl Fn(&TimedMoveDuration) -> f32,
+) -> Vec<&'a TimedMoveDuration> {
+    let mut ranked_moves = long_thinks.to_vec();
+    ranked_moves.sort_by(|a, b| key(b).total_cmp(&key(a)).then(a.ply.cmp(&b.ply)));
+    ranked_moves.truncate(CRITICAL_MOMENTS_PER_GAME);
+    ranked_moves
+}
+
+fn get_long_thinks_per_move_range(long_think_move_numbers: &[usize]) -> Vec<LongThinkMoveRange> {
+    let Some(last_move) = long_think_move_numbers.iter().max() else {
+        return Vec::new();
+    };
+
+    (0..last_move.div_ceil(MOVES_PER_RANGE))
+        .map(|range_index| {
+            let first_move = range_index * MOVES_PER_RANGE + 1;
+            let last_move = first_move + MOVES_PER_RANGE - 1;
+            let long_thinks_count = long_think_move_numbers
+                .iter()
+                .filter(|move_number| (first_move..=last_move).contains(*move_number))
+                .count();
+            LongThinkMoveRange {
+                first_move,
+                last_move,
+                long_thinks_count,
+                long_thinks_rate: long_thinks_count as f32 / long_think_move_numbers.len() as f32,
+            }
+        })
+        .collect()
+}
+
+// Only the long thinks are listed, a game played at an even pace has no critical moment.
+pub fn generate(
+    games: &[GameInfo],
+    skipped_games: &HashMap<usize, GameFetchWarning>,
+) -> LongThinksAnalysis {
+    let mut critical_moments = Vec::new();
+    let mut long_think_move_numbers = Vec::new();
+
+    for game_info in games.iter() {
+        if util::is_game_skipped(game_info, skipped_games) {
+            continue;
+        }
+        let move_durations = premove_generator::get_move_durations(game_info, true);
+        if move_durations.is_empty() {
+            continue;
+        }
+
+        let durations = move_durations
+            .iter()
+            .map(|move_duration| move_duration.duration as f32)
+            .collect::<Vec<f32>>();
+        let average_duration = util::compute_average(&durations);
+        let long_thinks = move_durations
+            .iter()
+            .filter(|move_duration| {
+                move_duration.duration as f32 >= average_duration * LONG_THINK_AVERAGE_RATIO
+            })
+            .collect::<Vec<&TimedMoveDuration>>();
+        if long_thinks.is_empty() {
+            continue;
+        }
+        long_think_move_numbers.extend(
+            long_thinks
+                .iter()
+                .map(|move_duration| move_duration.move_number),
+        );
+
+        critical_moments.push(GameCriticalMoments {
+            game_number: game_info.game_index + 1,
+            longest_thinks: get_top_moves(&long_thinks, |move_duration| {
+                move_duration.duration as f32
+            })
+            .into_iter()
+            .map(|move_duration| get_long_think(game_info, move_duration))
+            .collect(),
+            largest_clock_shares: get_top_moves(&long_thinks, get_clock_share)
+                .into_iter()
+                .map(|move_duration| get_long_think(game_info, move_duration))
+                .collect(),
+        });
+    }
+
+    LongThinksAnalysis {
+        critical_moments,
+        long_thinks_per_move_range: get_long_thinks_per_move_range(&long_think_move_numbers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate() {
+        // The user plays black and takes 2 seconds per move, except 20 seconds on the 12th
+        // move, with 158 seconds left before it.
+        let mut game = unit_test_util::get_some_mocked_complete_game(1000);
+        for (ply, clock) in game.clocks.as_mut().unwrap().iter_mut().enumerate() {
+            if ply % 2 == 1 && ply >= 23 {
+                *clock -= 1800;
+            }
+        }
+        let game_info = games_info_generator::generate(&game, &0, "user").unwrap();
+
+        let analysis = generate(&[game_info], &HashMap::new());
+        assert_eq!(analysis.critical_moments.len(), 1);
+        let critical_moments = &analysis.critical_moments[0];
+        assert_eq!(critical_moments.game_number, 1);
+
+        let longest_think = &critical_moments.longest_thinks[0];
+        assert_eq!(longest_think.ply, 24);
+        assert_eq!(longest_think.move_number, 12);
+        assert_eq!(longest_think.san, "Ng8");
+        assert_eq!(longest_think.clock_before_move, 158.0);
+        assert_eq!(longest_think.clock_after_move, 138.0);
+        assert_eq!(longest_think.think_time, 20.0);
+        assert_eq!(longest_think.clock_share, 20.0 / 158.0);
+        assert_eq!(
+            longest_think.analysis_url,
+            "https://lichess.org/game1000/black#24"
+        );
+        assert_eq!(critical_moments.longest_thinks.len(), 1);
+        assert_eq!(critical_moments.largest_clock_shares.len(), 1);
+        assert_eq!(critical_moments.largest_clock_shares[0].ply, 24);
+
+        assert_eq!(
+            analysis.long_thinks_per_move_range,
+            [
+                LongThinkMoveRange {
+                    first_move: 1,
+                    last_move: 10,
+                    long_thinks_count: 0,
+                    long_thinks_rate: 0.0,
+                },
+                LongThinkMoveRange {
+                    first_move: 11,
+                    last_move: 20,
+                    long_thinks_count: 1,
+                    long_thinks_rate: 1.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_flat_game() {
+        // The user takes 2 seconds on every move, none of them is a long think.
+        let game = unit_test_util::get_some_mocked_complete_game(1000);
+        let game_info = games_info_generator::generate(&game, &0, "user").unwrap();
+
+        let analysis = generate(&[game_info], &HashMap::new());
+        assert!(analysis.critical_moments.is_empty());
+        assert!(analysis.long_thinks_per_move_range.is_empty());
+    }
+}
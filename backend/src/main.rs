@@ -1,15 +1,32 @@
+mod clock_curve_generator;
+mod correspondence_generator;
 mod database;
 mod deserialization;
 mod errors_manager;
+mod export_generator;
 mod flagging_info_generator;
 mod games_info_generator;
 mod games_info_processor;
 mod insight_generator;
 mod lichess_client;
+mod localization;
+mod long_think_generator;
+mod premove_generator;
+mod progress_generator;
+mod rating_bucket_generator;
+mod report_generator;
 mod service_intermediary;
+mod session_generator;
+mod svg_chart_generator;
+mod time_of_day_generator;
+mod tournament_generator;
 mod trend_chart_generator;
+#[cfg(test)]
+mod mock_lichess_server;
+#[cfg(test)]
 mod unit_test_util;
 mod util;
+mod variant;
 mod websocket;
 
 use actix_cors::Cors;
@@ -22,11 +39,13 @@ const BIND_ADDRESS: &str = "127.0.0.1:8000";
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let app_state = web::Data::new(AppState {
-        websocket_session: Mutex::new(None)
+        websocket_session: Mutex::new(None),
+        lichess_base_url: lichess_client::LICHESS_BASE_URL.to_string(),
+        database_path: database::DATABASE_PATH.to_string(),
     });
 
-    database::create_database()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    database::create_database(&app_state.database_path)
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
 
     // Note: HttServer already implements graceful shutdown through ::shutdown_timeout().
     HttpServer::new(move || {
@@ -46,7 +65,21 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(cors)
             .app_data(app_state.clone())
+            // Malformed requests get the same structured error response as processing failures.
+            .app_data(
+                web::JsonConfig::default().error_handler(errors_manager::handle_malformed_request),
+            )
+            .app_data(
+                web::QueryConfig::default().error_handler(errors_manager::handle_malformed_request),
+            )
+            .app_data(
+                web::PathConfig::default().error_handler(errors_manager::handle_malformed_request),
+            )
             .service(service_intermediary::fetch_chess_data)
+            .service(service_intermediary::export_chess_data)
+            .service(service_intermediary::report_chess_data)
+            .service(service_intermediary::fetch_chess_chart)
+            .service(service_intermediary::fetch_progress)
             .service(web::resource("/ws").route(web::get().to(websocket::add_websocket_endpoint)))
             .wrap(middleware::Logger::default())
     })
@@ -0,0 +1,161 @@
+use crate::deserialization::GameJson;
+
+use actix_web::{web, App, HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CONNECTION_DROP_DELAY: Duration = Duration::from_millis(50);
+
+// One line of the NDJSON stream. The creation timestamp is kept aside so that malformed lines
+// can be filtered by the until parameter too.
+pub struct MockGameLine {
+    created_at: u64,
+    line: String,
+}
+
+impl MockGameLine {
+    pub fn game(game: &GameJson) -> Self {
+        MockGameLine {
+            created_at: game.created_at.unwrap_or_default(),
+            line: serde_json::to_string(game).unwrap(),
+        }
+    }
+
+    // A line recorded from a lichess export, kept verbatim.
+    pub fn recorded(line: &str) -> Self {
+        let created_at = serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|game| game["createdAt"].as_u64());
+        MockGameLine {
+            created_at: created_at.unwrap_or_default(),
+            line: line.to_string(),
+        }
+    }
+
+    pub fn malformed(created_at: u64) -> Self {
+        MockGameLine {
+            created_at,
+            line: String::from("{\"id\": \"malformed\", \"clocks\": ["),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MockUser {
+    pub games: Vec<MockGameLine>, // Most recent first, as streamed by lichess
+    pub truncate_after: Option<usize>, // The first response stops after this many lines
+    pub rate_limited_requests: Vec<usize>, // Indices of the requests answered with a 429
+    pub retry_after_in_seconds: u64, // Retry-After header of the 429 responses
+    pub line_delay: Option<Duration>, // Wait before sending each line, to mimic a slow stream
+    pub empty_after_truncation: bool, // The requests following the truncated one stream no game
+}
+
+struct MockLichessState {
+    users: HashMap<String, MockUser>,
+    requests: Mutex<Vec<String>>,
+    requests_count_per_user: Mutex<HashMap<String, usize>>,
+}
+
+// In-process fake of the lichess games export endpoint. Like lichess, it honours the max and
+// until query parameters and answers 404 for unknown users.
+pub struct MockLichessServer {
+    server: actix_test::TestServer,
+    state: web::Data<MockLichessState>,
+}
+
+impl MockLichessServer {
+    pub fn start(users: Vec<(&str, MockUser)>) -> Self {
+        let state = web::Data::new(MockLichessState {
+            users: users
+                .into_iter()
+                .map(|(username, user)| (username.to_string(), user))
+                .collect(),
+            requests: Mutex::new(Vec::new()),
+            requests_count_per_user: Mutex::new(HashMap::new()),
+        });
+
+        let app_state = state.clone();
+        let server = actix_test::start(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/api/games/user/{username}", web::get().to(export_games))
+        });
+
+        MockLichessServer { server, state }
+    }
+
+    pub fn get_base_url(&self) -> String {
+        format!("http://{}", self.server.addr())
+    }
+
+    // Path and query of every request received, in order.
+    pub fn get_requests(&self) -> Vec<String> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+async fn export_games(
+    req: HttpRequest,
+    username: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    state: web::Data<MockLichessState>,
+) -> HttpResponse {
+    state.requests.lock().unwrap().push(req.uri().to_string());
+
+    let Some(user) = state.users.get(username.as_str()) else {
+        return HttpResponse::NotFound().json(serde_json::json!({ "error": "Not found" }));
+    };
+
+    let request_idx = {
+        let mut requests_count_per_user = state.requests_count_per_user.lock().unwrap();
+        let requests_count = requests_count_per_user
+            .entry(username.to_string())
+            .or_insert(0);
+        *requests_count += 1;
+        *requests_count - 1
+    };
+    if user.rate_limited_requests.contains(&request_idx) {
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", user.retry_after_in_seconds.to_string()))
+            .finish();
+    }
+
+    let max = query.get("max").and_then(|max| max.parse::<usize>().ok());
+    let until = query
+        .get("until")
+        .and_then(|until| until.parse::<u64>().ok());
+    let mut lines = user
+        .games
+        .iter()
+        .filter(|game_line| until.is_none_or(|until| game_line.created_at <= until))
+        .take(max.unwrap_or(usize::MAX))
+        .filter(|_| request_idx == 0 || !user.empty_after_truncation)
+        .map(|game_line| format!("{}\n", game_line.line))
+        .collect::<Vec<String>>();
+
+    let truncate_after = user.truncate_after.filter(|_| request_idx == 0);
+    let is_truncated = truncate_after.is_some_and(|lines_count| lines_count < lines.len());
+    if let Some(lines_count) = truncate_after {
+        lines.truncate(lines_count);
+    }
+
+    // An error in the body stream aborts the connection before the response is complete. It is
+    // delayed so that the lines sent before it are flushed to the client.
+    let line_delay = user.line_delay.unwrap_or_default();
+    let body = stream::iter(lines)
+        .then(move |line| async move {
+            tokio::time::sleep(line_delay).await;
+            Ok::<Bytes, std::io::Error>(Bytes::from(line))
+        })
+        .chain(stream::iter(is_truncated.then_some(())).then(|_| async {
+            tokio::time::sleep(CONNECTION_DROP_DELAY).await;
+            Err(std::io::Error::other("Connection dropped"))
+        }));
+
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body)
+}
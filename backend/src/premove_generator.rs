@@ -0,0 +1,293 @@
+use crate::games_info_generator::GameInfo;
+use crate::games_info_processor::{self, GamesSummary};
+use crate::util;
+
+use serde::Serialize;
+
+// Moves played within this time are considered premoves or instant replies. Lichess records
+// the clocks in centiseconds and does not charge the premoves, so they usually take 0.
+const INSTANT_MOVE_THRESHOLD_IN_CENTISECONDS: i64 = 20;
+
+// Rough phase cut, the positions are not analysed: the opening depends on the variant, see
+// Variant::get_opening_moves_count, and the endgame starts after this move.
+const LAST_MIDDLEGAME_MOVE: usize = 30;
+
+// A player is in time trouble when less than this share of their starting clock is left.
+const TIME_TROUBLE_CLOCK_RATE: f32 = 0.1;
+
+#[derive(Serialize, Debug, PartialEq, Default)]
+pub struct InstantMoveRate {
+    pub moves_count: usize,
+    pub instant_moves_count: usize,
+    pub rate: Option<f32>, // None when no move was timed
+}
+
+impl InstantMoveRate {
+    fn add(&mut self, is_instant: bool) {
+        self.moves_count += 1;
+        if is_instant {
+            self.instant_moves_count += 1;
+        }
+        self.rate = Some(self.instant_moves_count as f32 / self.moves_count as f32);
+    }
+}
+
+// The premove reliance of the user is the rate of instant moves over all their timed moves.
+#[derive(Serialize, Debug, PartialEq, Default)]
+pub struct PremoveAnalysis {
+    pub instant_move_threshold_in_seconds: f32,
+    pub premove_reliance: InstantMoveRate,
+    pub opening: InstantMoveRate,
+    pub middlegame: InstantMoveRate,
+    pub endgame: InstantMoveRate,
+    pub time_trouble: InstantMoveRate,
+    pub opponent_premove_reliance: InstantMoveRate,
+    // Games in which the user moved instantly more often than on average, and the others.
+    pub frequent_instant_moves_games: GamesSummary,
+    pub other_games: GamesSummary,
+    pub results_degrade_with_instant_moves: bool,
+}
+
+// Clocks and duration in centiseconds.
+#[derive(Debug, PartialEq)]
+pub struct TimedMoveDuration {
+    pub ply: usize, // Starting at 1, as in the lichess analysis board
+    pub move_number: usize,
+    pub clock_before_move: i64,
+    pub clock_after_move: i64,
+    pub starting_clock: i64,
+    pub duration: i64,
+}
+
+// Lichess does not run the clocks for the first move of each player, so their moves are timed
+// from the second one on. Berserking players get no increment.
+pub fn get_move_durations(game_info: &GameInfo, of_user: bool) -> Vec<TimedMoveDuration> {
+    let is_white = (game_info.user_color == "white") == of_user;
+    let has_berserked = if of_user {
+        game_info.user_berserked
+    } else {
+        game_info.opponent_berserked
+    };
+    let increment = if has_berserked {
+        0
+    } else {
+        game_info.increment
+    };
+
+    let clocks = game_info
+        .timed_moves
+        .iter()
+        .skip(if is_white { 0 } else { 1 })
+        .step_by(2)
+        .map(|timed_move| timed_move.move_time)
+        .collect::<Vec<i64>>();
+    let Some(starting_clock) = clocks.first().copied() else {
+        return Vec::new();
+    };
+
+    clocks
+        .windows(2)
+        .enumerate()
+        .map(|(i, clocks)| TimedMoveDuration {
+            ply: 2 * (i + 1) + if is_white { 1 } else { 2 },
+            move_number: i + 2,
+            clock_before_move: clocks[0],
+            clock_after_move: clocks[1],
+            starting_clock,
+            duration: clocks[0] - clocks[1] + increment,
+        })
+        .collect()
+}
+
+fn is_instant(move_duration: &TimedMoveDuration) -> bool {
+    move_duration.duration <= INSTANT_MOVE_THRESHOLD_IN_CENTISECONDS
+}
+
+fn is_in_time_trouble(move_duration: &TimedMoveDuration) -> bool {
+    (move_duration.clock_before_move as f32)
+        < move_duration.starting_clock as f32 * TIME_TROUBLE_CLOCK_RATE
+}
+
+// Whether the clock of the player went below the time trouble threshold at some point, the
+// last move included.
+pub fn has_reached_time_trouble(move_durations: &[TimedMoveDuration]) -> bool {
+    move_durations.iter().any(|move_duration| {
+        (move_duration.clock_after_move as f32)
+            < move_duration.starting_clock as f32 * TIME_TROUBLE_CLOCK_RATE
+    })
+}
+
+pub fn generate(games_with_differentials: &[(&GameInfo, f32)]) -> PremoveAnalysis {
+    let mut premove_reliance = InstantMoveRate::default();
+    let mut opening = InstantMoveRate::default();
+    let mut middlegame = InstantMoveRate::default();
+    let mut endgame = InstantMoveRate::default();
+    let mut time_trouble = InstantMoveRate::default();
+    let mut opponent_premove_reliance = InstantMoveRate::default();
+    let mut game_rates = Vec::new();
+
+    for (game_info, time_differential) in games_with_differentials {
+        let opening_moves_count = game_info.variant.get_opening_moves_count().unwrap_or(0);
+        let mut game_rate = InstantMoveRate::default();
+        for move_duration in get_move_durations(game_info, true) {
+            let is_instant = is_instant(&move_duration);
+            premove_reliance.add(is_instant);
+            game_rate.add(is_instant);
+
+            let phase = if move_duration.move_number <= opening_moves_count {
+                &mut opening
+            } else if move_duration.move_number <= LAST_MIDDLEGAME_MOVE {
+                &mut middlegame
+            } else {
+                &mut endgame
+            };
+            phase.add(is_instant);
+            if is_in_time_trouble(&move_duration) {
+                time_trouble.add(is_instant);
+            }
+        }
+        for move_duration in get_move_durations(game_info, false) {
+            opponent_premove_reliance.add(is_instant(&move_duration));
+        }
+        if let Some(rate) = game_rate.rate {
+            game_rates.push(((*game_info, *time_differential), rate));
+        }
+    }
+
+    let rates = game_rates
+        .iter()
+        .map(|(_, rate)| *rate)
+        .collect::<Vec<f32>>();
+    let average_rate = if rates.is_empty() {
+        0.0
+    } else {
+        util::compute_average(&rates)
+    };
+    let (frequent_games, other_games): (Vec<_>, Vec<_>) = game_rates
+        .into_iter()
+        .partition(|(_, rate)| *rate > average_rate);
+    let frequent_instant_moves_games = games_info_processor::summarize_games(
+        &frequent_games
+            .into_iter()
+            .map(|(game, _)| game)
+            .collect::<Vec<(&GameInfo, f32)>>(),
+    );
+    let other_games = games_info_processor::summarize_games(
+        &other_games
+            .into_iter()
+            .map(|(game, _)| game)
+            .collect::<Vec<(&GameInfo, f32)>>(),
+    );
+
+    PremoveAnalysis {
+        instant_move_threshold_in_seconds: util::convert_centiseconds_to_seconds(
+            INSTANT_MOVE_THRESHOLD_IN_CENTISECONDS as i32,
+        ),
+        premove_reliance,
+        opening,
+        middlegame,
+        endgame,
+        time_trouble,
+        opponent_premove_reliance,
+        results_degrade_with_instant_moves: util::is_significantly_lower(
+            &frequent_instant_moves_games,
+            &other_games,
+        ),
+        frequent_instant_moves_games,
+        other_games,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit_test_util::MinimalGameJsonInfoTesting;
+    use crate::{games_info_generator, unit_test_util};
+
+    // A 180+2 game played as white by "user", whose clocks are given per move. The opponent
+    // always takes 1 second.
+    fn make_game(user_clocks: &[i64], winner: &str) -> GameInfo {
+        let mut opponent_clock = 18000;
+        let clocks = user_clocks
+            .iter()
+            .flat_map(|user_clock| {
+                opponent_clock += 100; // 1 second spent, 2 seconds of increment
+                [*user_clock, opponent_clock]
+            })
+            .collect::<Vec<i64>>();
+        let mut game = unit_test_util::create_mock_game_json(MinimalGameJsonInfoTesting {
+            moves: Some(vec!["Nf3 Nf6 Ng1 Ng8"; clocks.len() / 4].join(" ")),
+            clocks: Some(clocks),
+            black_player_name: Some("other_user".to_string()),
+            white_player_name: Some("user".to_string()),
+            winner: Some(winner.to_string()),
+        });
+        game.clock.as_mut().unwrap().increment = Some(2);
+        games_info_generator::generate(&game, &0, "user").unwrap()
+    }
+
+    #[test]
+    fn test_get_move_durations() {
+        // The increment is added back: the second move took 1 second, the third was instant.
+        let game_info = make_game(&[18000, 18100, 18300, 18400], "white");
+        let durations = get_move_durations(&game_info, true)
+            .iter()
+            .map(|move_duration| {
+                (
+                    move_duration.ply,
+                    move_duration.move_number,
+                    move_duration.duration,
+                )
+            })
+            .collect::<Vec<(usize, usize, i64)>>();
+        assert_eq!(durations, [(3, 2, 100), (5, 3, 0), (7, 4, 100)]);
+        let opponent_durations = get_move_durations(&game_info, false);
+        assert_eq!(opponent_durations[0].ply, 4);
+        assert!(opponent_durations
+            .iter()
+            .all(|move_duration| move_duration.duration == 100));
+    }
+
+    #[test]
+    fn test_generate() {
+        // 40 moves: instant moves from the 31st on, except the 36th, in time trouble for the
+        // 37th to the 39th.
+        let user_clocks = (1..=40)
+            .map(|move_number| match move_number {
+                1..=30 => 18000 - (move_number - 1) * 500,
+                31..=35 => 3500 + (move_number - 30) * 200,
+                _ => 1000 + (move_number - 35) * 200,
+            })
+            .collect::<Vec<i64>>();
+        let premove_game = make_game(&user_clocks, "black");
+        let steady_game = make_game(
+            &(1..=40)
+                .map(|move_number| 18000 - (move_number - 1) * 100)
+                .collect::<Vec<i64>>(),
+            "white",
+        );
+        let games = [
+            &premove_game,
+            &premove_game,
+            &premove_game,
+            &steady_game,
+            &steady_game,
+            &steady_game,
+        ]
+        .map(|game_info| (game_info, 0.0));
+
+        let analysis = generate(&games);
+        assert_eq!(analysis.instant_move_threshold_in_seconds, 0.2);
+        assert_eq!(analysis.premove_reliance.moves_count, 6 * 39);
+        assert_eq!(analysis.premove_reliance.instant_moves_count, 3 * 9);
+        assert_eq!(analysis.opening.rate, Some(0.0));
+        assert_eq!(analysis.middlegame.rate, Some(0.0));
+        assert_eq!(analysis.endgame.rate, Some(27.0 / 60.0));
+        assert_eq!(analysis.time_trouble.rate, Some(1.0));
+        assert_eq!(analysis.opponent_premove_reliance.rate, Some(0.0));
+        assert_eq!(analysis.frequent_instant_moves_games.games_count, 3);
+        assert_eq!(analysis.frequent_instant_moves_games.win_rate, Some(0.0));
+        assert_eq!(analysis.other_games.win_rate, Some(1.0));
+        assert!(analysis.results_degrade_with_instant_moves);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::service_intermediary::ProgressQuery;
+
+use serde::Serialize;
+
+const DEFAULT_PERIOD_IN_DAYS: u64 = 30;
+const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+
+// Headline metrics of an analysis, stored after each analysis requested by the frontend.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct AnalysisSnapshot {
+    pub created_at: i64, // Unix timestamp in milliseconds
+    pub perf_type: String,
+    pub user_color: String,
+    pub games_count: i32, // As requested, some games may have been left out of the analysis
+    pub analysed_games_count: i32,
+    pub average_time: Option<f32>, // None for the correspondence games
+    pub win_rate: f32,
+    // Flags per analysed game, so that analyses of different sizes compare. None without any
+    // analysed game.
+    pub user_flag_rate: Option<f32>,
+    pub opponent_flag_rate: Option<f32>,
+    pub consistency_score: Option<f32>,
+}
+
+// Differences between the latest snapshot and the one it is compared with.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ProgressChange {
+    pub since: i64, // created_at of the compared snapshot
+    pub average_time: Option<f32>,
+    pub win_rate: f32,
+    pub user_flag_rate: Option<f32>,
+    pub opponent_flag_rate: Option<f32>,
+    pub consistency_score: Option<f32>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ProgressResponse {
+    pub username: String,
+    pub perf_type: String,
+    pub period_in_days: u64,
+    pub snapshots: Vec<AnalysisSnapshot>, // Chronological
+    pub change: Option<ProgressChange>,   // None with fewer than two snapshots
+}
+
+fn get_difference(latest: Option<f32>, baseline: Option<f32>) -> Option<f32> {
+    Some(latest? - baseline?)
+}
+
+// The latest snapshot is compared with the last one taken at least a period earlier, or with
+// the first one when the history is shorter than the period.
+fn get_change(snapshots: &[AnalysisSnapshot], period_in_days: u64) -> Option<ProgressChange> {
+    let (latest, previous_snapshots) = snapshots.split_last()?;
+    let period_start = latest.created_at - period_in_days as i64 * MILLISECONDS_PER_DAY;
+    let baseline = previous_snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.created_at <= period_start)
+        .or(previous_snapshots.first())?;
+
+    Some(ProgressChange {
+        since: baseline.created_at,
+        average_time: get_difference(latest.average_time, baseline.average_time),
+        win_rate: latest.win_rate - baseline.win_rate,
+        user_flag_rate: get_difference(latest.user_flag_rate, baseline.user_flag_rate),
+        opponent_flag_rate: get_difference(latest.opponent_flag_rate, baseline.opponent_flag_rate),
+        consistency_score: get_difference(latest.consistency_score, baseline.consistency_score),
+    })
+}
+
+pub fn generate(
+    username: &str,
+    query: &ProgressQuery,
+    snapshots: Vec<AnalysisSnapshot>,
+) -> ProgressResponse {
+    let snapshots = snapshots
+        .into_iter()
+        .filter(|snapshot| {
+            query
+                .user_color
+                .as_ref()
+                .is_none_or(|user_color| snapshot.user_color == *user_color)
+        })
+        .collect::<Vec<AnalysisSnapshot>>();
+    let period_in_days = query.period_days.unwrap_or(DEFAULT_PERIOD_IN_DAYS);
+
+    ProgressResponse {
+        username: username.to_string(),
+        perf_type: query.get_perf_type(),
+        period_in_days,
+        change: get_change(&snapshots, period_in_days),
+        snapshots,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_snapshot(day: i64, user_color: &str, win_rate: f32) -> AnalysisSnapshot {
+        AnalysisSnapshot {
+            created_at: day * MILLISECONDS_PER_DAY,
+            perf_type: String::from("blitz"),
+            user_color: user_color.to_string(),
+            games_count: 50,
+            analysed_games_count: 50,
+            average_time: Some(day as f32),
+            win_rate,
+            user_flag_rate: Some(0.04),
+            opponent_flag_rate: Some(0.08),
+            consistency_score: None,
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let snapshots = vec![
+            make_snapshot(1, "both", 0.4),
+            make_snapshot(10, "both", 0.5),
+            make_snapshot(20, "white", 0.9),
+            make_snapshot(45, "both", 0.55),
+        ];
+        let mut query = ProgressQuery {
+            game_mode: String::from("blitz"),
+            variant: None,
+            user_color: Some(String::from("both")),
+            period_days: None,
+            locale: None,
+        };
+
+        // The snapshot of day 10 is the last one taken 30 days before the latest one.
+        let progress = generate("user", &query, snapshots.clone());
+        assert_eq!(progress.perf_type, "blitz");
+        assert_eq!(progress.snapshots.len(), 3);
+        let change = progress.change.unwrap();
+        assert_eq!(change.since, 10 * MILLISECONDS_PER_DAY);
+        assert_eq!(change.average_time, Some(35.0));
+        assert_eq!(change.win_rate, 0.55 - 0.5);
+        assert_eq!(change.user_flag_rate, Some(0.0));
+        assert_eq!(change.consistency_score, None);
+
+        // Without a snapshot old enough, the first one is the baseline.
+        query.period_days = Some(90);
+        query.user_color = None;
+        let change = generate("user", &query, snapshots.clone()).change.unwrap();
+        assert_eq!(change.since, MILLISECONDS_PER_DAY);
+
+        assert_eq!(
+            generate("user", &query, snapshots[..1].to_vec()).change,
+            None
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::games_info_generator::GameInfo;
+use crate::games_info_processor::{self, GamesSummary};
+
+use serde::Serialize;
+
+// Boundaries of the rating difference brackets (opponent rating - user rating). A positive
+// difference means that the opponent was higher rated.
+const RATING_DIFFERENCE_BOUNDARIES: [i32; 4] = [-200, -50, 50, 200];
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct OpponentRatingBucket {
+    pub label: String,
+    pub min_rating_difference: Option<i32>, // Inclusive, None when unbounded
+    pub max_rating_difference: Option<i32>, // Exclusive, None when unbounded
+    #[serde(flatten)]
+    pub summary: GamesSummary,
+}
+
+impl OpponentRatingBucket {
+    pub fn contains(&self, rating_difference: i32) -> bool {
+        self.min_rating_difference
+            .is_none_or(|min| rating_difference >= min)
+            && self
+                .max_rating_difference
+                .is_none_or(|max| rating_difference < max)
+    }
+
+    // Opponents rated clearly above the user.
+    pub fn is_higher_rated(&self) -> bool {
+        self.min_rating_difference.is_some_and(|min| min > 0)
+    }
+}
+
+pub fn get_rating_difference(game_info: &GameInfo) -> i32 {
+    game_info.opponent_rating - game_info.user_rating
+}
+
+fn get_label(min: Option<i32>, max: Option<i32>) -> String {
+    match (min, max) {
+        (None, Some(max)) => format!("< {}", max),
+        (Some(min), None) => format!(">= {}", min),
+        (Some(min), Some(max)) => format!("{}..{}", min, max),
+        (None, None) => String::from("all"),
+    }
+}
+
+fn get_empty_buckets() -> Vec<OpponentRatingBucket> {
+    let mut bounds = vec![None];
+    bounds.extend(
+        RATING_DIFFERENCE_BOUNDARIES
+            .iter()
+            .map(|bound| Some(*bound)),
+    );
+    bounds.push(None);
+
+    bounds
+        .windows(2)
+        .map(|window| OpponentRatingBucket {
+            label: get_label(window[0], window[1]),
+            min_rating_difference: window[0],
+            max_rating_difference: window[1],
+            summary: GamesSummary::default(),
+        })
+        .collect()
+}
+
+pub fn generate(games_with_differentials: &[(&GameInfo, f32)]) -> Vec<OpponentRatingBucket> {
+    let mut buckets = get_empty_buckets();
+
+    for bucket in buckets.iter_mut() {
+        let games_in_bucket = games_with_differentials
+            .iter()
+            .filter(|(game_info, _)| {
+                // The lichess AI and anonymous players have no rating to compare with.
+                game_info.opponent_kind.has_rating()
+                    && bucket.contains(get_rating_difference(game_info))
+            })
+            .copied()
+            .collect::<Vec<(&GameInfo, f32)>>();
+        bucket.summary = games_info_processor::summarize_games(&games_in_bucket);
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate() {
+        // Game a: user (black, 2054) vs 2000, game b: user (white, 2000) vs 2054.
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let game_b = unit_test_util::get_some_mocked_game_b();
+        let input_games = [
+            games_info_generator::generate(&game_a, &0, "user").unwrap(),
+            games_info_generator::generate(&game_b, &1, "user").unwrap(),
+        ];
+        let games_with_differentials = vec![(&input_games[0], -0.08), (&input_games[1], 5.78)];
+
+        let buckets = generate(&games_with_differentials);
+        let labels = buckets
+            .iter()
+            .map(|bucket| bucket.label.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            labels,
+            ["< -200", "-200..-50", "-50..50", "50..200", ">= 200"]
+        );
+
+        assert_eq!(buckets[1].summary.games_count, 1);
+        assert_eq!(buckets[1].summary.average_time_differential, Some(-0.08));
+        assert_eq!(buckets[3].summary.games_count, 1);
+        assert_eq!(buckets[3].summary.win_rate, Some(0.0));
+        assert!(buckets[3].is_higher_rated());
+        assert_eq!(buckets[2].summary, GamesSummary::default());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::errors_manager::ProcessError;
+use crate::localization::{self, Message};
+use crate::service_intermediary::{
+    ChessDataRequest, ChessDataResponse, DescriptionMessageAssessment, PlayerAnalysis,
+};
+use crate::svg_chart_generator;
+use crate::variant;
+
+use std::fmt::Write;
+
+const REPORT_STYLESHEET: &str = "\
+body { font-family: Inter, sans-serif; margin: 2rem auto; max-width: 720px; color: #111827; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.1rem; margin-top: 2rem; }
+.summary { display: flex; gap: 2rem; }
+.message { padding: 0.75rem 1rem; border-radius: 0.5rem; }
+.positive { background: #D1FAE5; }
+.neutral { background: #F3F4F6; }
+.negative { background: #FEE2E2; }
+.suggestion { font-size: 0.9rem; color: #374151; }
+footer { margin-top: 2rem; color: #6B7280; font-size: 0.8rem; }";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn get_assessment_css_class(assessment: &DescriptionMessageAssessment) -> &'static str {
+    match assessment {
+        DescriptionMessageAssessment::Positive => "positive",
+        DescriptionMessageAssessment::Neutral => "neutral",
+        DescriptionMessageAssessment::Negative => "negative",
+    }
+}
+
+// Renders a self-contained HTML page (inline CSS and SVG, no scripts) so that it can be
+// emailed or archived as a single file.
+pub fn generate(
+    request_data: &ChessDataRequest,
+    response: &ChessDataResponse,
+) -> Result<String, ProcessError> {
+    let ChessDataResponse::RequestFromFrontend(player_analysis) = response else {
+        return Err(ProcessError::DataError {
+            message: Message::ReportRequiresPlayerAnalysis,
+        });
+    };
+    let PlayerAnalysis {
+        time,
+        insights,
+        games_with_errors,
+        trend_chart_data,
+        player_win_rate_in_fetched_games,
+        players_flag_counts,
+        ..
+    } = player_analysis.as_ref();
+
+    let win_rate = player_win_rate_in_fetched_games
+        .parse::<f32>()
+        .unwrap_or(0.0);
+    let locale = localization::get_locale(request_data);
+    let username = escape_html(&request_data.username);
+    let localized_html = |message: Message| escape_html(&message.localize(locale));
+    let average_time_message = match time.parse::<f32>() {
+        Ok(seconds) => Message::ReportAverageTime { seconds },
+        Err(_) => Message::TimeNotComputed,
+    };
+
+    let mut html = String::new();
+    write!(
+        html,
+        "<!DOCTYPE html><html lang=\"{}\"><head><meta charset=\"utf-8\">\
+         <title>Chess Pace Tracker - {username}</title><style>{REPORT_STYLESHEET}</style>\
+         </head><body>",
+        locale.get_language_tag()
+    )?;
+    write!(
+        html,
+        "<h1>{}</h1><p>{} <b>{}</b></p>",
+        localized_html(Message::ReportHeading {
+            username: request_data.username.clone()
+        }),
+        localized_html(Message::ReportGamesSummary {
+            games_count: request_data.games_count,
+            perf_type: variant::get_perf_type(request_data),
+            user_color: request_data.user_color.clone()
+        }),
+        localized_html(average_time_message)
+    )?;
+    for insight in insights {
+        write!(
+            html,
+            "<p class=\"message {}\">{}",
+            get_assessment_css_class(&insight.assessment),
+            escape_html(&insight.message)
+        )?;
+        if let Some(suggestion) = &insight.suggestion {
+            write!(
+                html,
+                "<br><span class=\"suggestion\">{}</span>",
+                escape_html(suggestion)
+            )?;
+        }
+        html.push_str("</p>");
+    }
+
+    write!(
+        html,
+        "<h2>{}</h2>{}",
+        localized_html(Message::ReportTrendChartHeading),
+        svg_chart_generator::generate_trend_chart(trend_chart_data, locale)?
+    )?;
+    write!(
+        html,
+        "<div class=\"summary\"><div><h2>{}</h2>{}</div><div><h2>{}</h2>{}</div></div>",
+        localized_html(Message::ReportWinRateHeading),
+        svg_chart_generator::generate_win_rate_donut_chart(win_rate)?,
+        localized_html(Message::ReportFlagsHeading),
+        svg_chart_generator::generate_flag_counts_chart(
+            *players_flag_counts,
+            trend_chart_data.len(),
+            locale
+        )?
+    )?;
+
+    if !games_with_errors.is_empty() {
+        write!(
+            html,
+            "<h2>{}</h2><ul>",
+            localized_html(Message::ReportExcludedGamesHeading)
+        )?;
+        for (game_number, error_message) in games_with_errors {
+            write!(
+                html,
+                "<li>{}: {}</li>",
+                localized_html(Message::GameNumber {
+                    game_number: *game_number as i32
+                }),
+                escape_html(error_message)
+            )?;
+        }
+        html.push_str("</ul>");
+    }
+
+    write!(
+        html,
+        "<footer>{}</footer></body></html>",
+        localized_html(Message::ReportFooter)
+    )?;
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::insight_generator::{Insight, InsightCategory, InsightSeverity};
+    use crate::localization::Locale;
+    use crate::trend_chart_generator::{self, TrendChartSmoothing};
+    use crate::{
+        games_info_generator, long_think_generator, premove_generator, session_generator,
+        time_of_day_generator, unit_test_util,
+    };
+    use std::collections::HashMap;
+
+    fn make_request_data(username: &str) -> ChessDataRequest {
+        ChessDataRequest {
+            username: username.to_string(),
+            games_count: 2,
+            game_mode: "blitz".to_string(),
+            user_color: "both".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let game_b = unit_test_util::get_some_mocked_game_b();
+        let input_games = vec![
+            games_info_generator::generate(&game_a, &0, "user").unwrap(),
+            games_info_generator::generate(&game_b, &1, "user").unwrap(),
+        ];
+        let trend_chart_data = trend_chart_generator::generate(
+            &input_games,
+            &HashMap::new(),
+            vec![-0.08, 5.78],
+            &TrendChartSmoothing::default(),
+        );
+
+        let response = ChessDataResponse::new(PlayerAnalysis {
+            variant: variant::Variant::Standard,
+            time: "1.5".to_string(),
+            explanation_message: (
+                "Some <message>".to_string(),
+                DescriptionMessageAssessment::Positive,
+            ),
+            insights: vec![Insight {
+                category: InsightCategory::TimeManagement,
+                severity: InsightSeverity::Info,
+                assessment: DescriptionMessageAssessment::Positive,
+                message: "Some <message>".to_string(),
+                suggestion: Some("Some suggestion".to_string()),
+            }],
+            games_with_errors: vec![(3, "Game does not have enough moves.".to_string())],
+            trend_chart_data,
+            trend_line: None,
+            trend_chart_buckets: Vec::new(),
+            clock_curves: Vec::new(),
+            average_clock_curve: Vec::new(),
+            opponent_rating_buckets: Vec::new(),
+            opponent_kinds: Vec::new(),
+            tournament: None,
+            premoves: premove_generator::generate(&[]),
+            long_thinks: long_think_generator::generate(&[], &HashMap::new()),
+            consistency: None,
+            sessions: session_generator::generate(&[], 30),
+            time_of_day: time_of_day_generator::generate(&[], chrono_tz::Tz::UTC),
+            player_win_rate_in_fetched_games: "0.50".to_string(),
+            players_flag_counts: (1, 0),
+            analysed_games_count: 2,
+            correspondence: None,
+        });
+
+        let html = generate(&make_request_data("<user>"), &response).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;user&gt;"));
+        assert!(html.contains("<html lang=\"en\">"));
+        assert!(html.contains(
+            "2 blitz games as both colors. <b>Average half time differential: 1.50 seconds.</b>"
+        ));
+        assert!(html.contains("Some &lt;message&gt;<br><span class=\"suggestion\">Some suggestion"));
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert!(html.contains("<li>Game 3: Game does not have enough moves.</li>"));
+        assert!(!html.contains("<script"));
+
+        let french_request_data = ChessDataRequest {
+            locale: Some(Locale::Fr),
+            ..make_request_data("user")
+        };
+        let html = generate(&french_request_data, &response).unwrap();
+        assert!(html.contains("<html lang=\"fr\">"));
+        assert!(html.contains("<h1>Rapport de gestion du temps de user</h1>"));
+        assert!(html.contains("<li>Partie 3: Game does not have enough moves.</li>"));
+        assert!(html.contains(">Victoires au temps</text>"));
+
+        let internal_response = ChessDataResponse::new_internal("1.5".to_string(), Vec::new());
+        assert!(generate(&make_request_data("user"), &internal_response).is_err());
+    }
+}
@@ -1,22 +1,33 @@
-use std::collections::HashMap;
 use std::time::Instant;
 
+use crate::clock_curve_generator::{ClockCurvePoint, GameClockCurve};
+use crate::correspondence_generator::CorrespondenceAnalysis;
 use crate::database;
-use crate::deserialization;
-use crate::lichess_client;
-use crate::trend_chart_generator::TrendChartDatum;
+use crate::errors_manager::{self, ProcessError};
+use crate::games_info_processor::{ConsistencyScore, OpponentKindSummary};
+use crate::insight_generator::Insight;
+use crate::lichess_client::{self, LichessToken};
+use crate::localization::{self, Locale, Message};
+use crate::long_think_generator::LongThinksAnalysis;
+use crate::premove_generator::PremoveAnalysis;
+use crate::progress_generator;
+use crate::rating_bucket_generator::OpponentRatingBucket;
+use crate::session_generator::SessionsAnalysis;
+use crate::time_of_day_generator::TimeOfDayAnalysis;
+use crate::tournament_generator::TournamentAnalysis;
+use crate::trend_chart_generator::{TrendChartBucket, TrendChartDatum, TrendLine};
+use crate::variant::Variant;
 use crate::websocket;
 use crate::websocket::StopWebsocket;
 use crate::websocket::WebSocketSession;
 
-use actix_web::ResponseError;
-use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
 use actix::Addr;
 use websocket::AppState;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Clone, Copy)]
 #[repr(i32)]
 pub enum DescriptionMessageAssessment {
     Positive = 0,
@@ -28,34 +39,167 @@ pub enum DescriptionMessageAssessment {
 pub enum GameFetchWarning {
     InternalErrorOccuredWhileProcessingAGame = 0,
     GameHasNotEnoughMoves,
+    GameHasNoClocks, // Correspondence games, see correspondence_generator
+    GameHasMissingClocks, // Timed games whose clocks were not all recorded
+    GameWasAborted,
+    AnonymousOpponent,
+    ComputerOpponent,
+    CasualGame,         // Only fetched along with the lichess AI and anonymous games
+    TournamentGame,     // Left out by the tournament filter of the request
+    NotATournamentGame, // Left out by the tournament filter of the request
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
 pub enum GlobalFetchError {
     RequestedMoreGamesThanAvailableInTheUserDatabase,
     NotEnoughGamesToComputeAverage, // n == 0
+    StreamResumptionFailed,         // The connection dropped before the first game
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct ChessDataRequest {
     pub username: String,
     pub games_count: i32,
     pub game_mode: String,
     pub user_color: String,
+    pub variant: Option<Variant>, // Lichess variant of the games, standard if unset
+    pub include_computer_opponents: Option<bool>, // The lichess AI, excluded if unset
+    pub include_anonymous_opponents: Option<bool>, // Excluded if unset
+    pub tournament_games: Option<TournamentGamesFilter>, // Included with the others if unset
     pub user_elo: Option<i32>, // For internal uses only
+    pub rolling_window: Option<usize>, // Number of games in the trend chart rolling windows
+    pub exponential_smoothing_factor: Option<f32>, // In ]0, 1], higher favors recent games
+    pub trend_chart_bucket: Option<TrendChartBucketSize>, // Aggregate the trend chart by period
+    pub session_gap_minutes: Option<u64>, // Max break between two games of the same session
+    pub timezone: Option<String>, // IANA name used for the time of day breakdown, UTC if unset
+    pub locale: Option<Locale>, // Language of the displayed messages, see Accept-Language if unset
+    #[serde(skip)]
+    pub lichess_token: Option<LichessToken>, // From the Authorization header, never the body
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TournamentGamesFilter {
+    #[default]
+    Include,
+    Only,
+    Exclude,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendChartBucketSize {
+    Day,
+    Week,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    pub format: ExportFormat,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartKind {
+    Trend,
+    WinRate,
+    ClockCurve,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl ChartFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ChartFormat::Svg => "image/svg+xml",
+            ChartFormat::Png => "image/png",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChartQuery {
+    #[serde(default)]
+    pub format: ChartFormat,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProgressQuery {
+    pub game_mode: String,
+    pub variant: Option<Variant>,   // Standard if unset
+    pub user_color: Option<String>, // Every color filter if unset
+    pub period_days: Option<u64>,   // 30 if unset
+    pub locale: Option<Locale>,     // Accept-Language if unset
+}
+
+impl ProgressQuery {
+    pub fn get_perf_type(&self) -> String {
+        self.variant
+            .unwrap_or_default()
+            .get_perf_type(&self.game_mode)
+    }
+}
+
+// Everything the frontend displays about the analysed games.
+#[derive(Serialize)]
+pub struct PlayerAnalysis {
+    pub variant: Variant,
+    pub time: String,
+    pub explanation_message: (String, DescriptionMessageAssessment),
+    pub insights: Vec<Insight>, // Ranked, the first one is the explanation message
+    pub games_with_errors: Vec<(usize, String)>,
+    pub trend_chart_data: Vec<TrendChartDatum>,
+    pub trend_line: Option<TrendLine>,
+    pub trend_chart_buckets: Vec<TrendChartBucket>,
+    pub clock_curves: Vec<GameClockCurve>,
+    pub average_clock_curve: Vec<ClockCurvePoint>,
+    pub opponent_rating_buckets: Vec<OpponentRatingBucket>,
+    pub opponent_kinds: Vec<OpponentKindSummary>,
+    pub tournament: Option<TournamentAnalysis>, // None when no game was played in a tournament
+    pub premoves: PremoveAnalysis,
+    pub long_thinks: LongThinksAnalysis,
+    pub consistency: Option<ConsistencyScore>, // None when no game has a half time differential
+    pub sessions: SessionsAnalysis,
+    pub time_of_day: TimeOfDayAnalysis,
+    pub player_win_rate_in_fetched_games: String,
+    pub players_flag_counts: (i32, i32),
+    pub analysed_games_count: usize, // Games kept in the analysis, the flag counts are out of them
+    pub correspondence: Option<CorrespondenceAnalysis>, // None without correspondence games
 }
 
 #[derive(Serialize)]
 #[serde(untagged)]
 pub enum ChessDataResponse {
-    RequestFromFrontend {
-        time: String,
-        explanation_message: (String, DescriptionMessageAssessment),
-        games_with_errors: Vec<(usize, String)>,
-        trend_chart_data: Vec<TrendChartDatum>,
-        player_win_rate_in_fetched_games: String,
-        players_flag_counts: (i32, i32),
-    },
+    RequestFromFrontend(Box<PlayerAnalysis>),
     RequestFromDatabase {
         time: String,
         players_considered: Vec<(String, i32)>,
@@ -63,25 +207,8 @@ pub enum ChessDataResponse {
 }
 
 impl ChessDataResponse {
-    pub fn new(
-        time: String,
-        explanation_message: (String, DescriptionMessageAssessment),
-        games_with_errors: HashMap<usize, GameFetchWarning>,
-        trend_chart_data: Vec<TrendChartDatum>,
-        player_win_rate_in_fetched_games: String,
-        players_flag_counts: (i32, i32),
-    ) -> Self {
-        let errors_vec =
-        deserialization::convert_games_with_errors_to_displayable_format(games_with_errors);
-
-        ChessDataResponse::RequestFromFrontend {
-            time,
-            explanation_message,
-            games_with_errors: errors_vec,
-            trend_chart_data,
-            player_win_rate_in_fetched_games,
-            players_flag_counts,
-        }
+    pub fn new(player_analysis: PlayerAnalysis) -> Self {
+        ChessDataResponse::RequestFromFrontend(Box::new(player_analysis))
     }
 
     pub fn new_internal(time: String, players_considered: Vec<(String, i32)>) -> Self {
@@ -104,10 +231,7 @@ impl RequestSource {
             Some("frontend") => RequestSource::Frontend,
             Some("internal") => RequestSource::Internal,
             _ => {
-                assert!(
-                    true,
-                    "Request source not recognized. Defaulting to 'frontend'"
-                );
+                // Request source not recognized. Defaulting to 'frontend'.
                 RequestSource::Frontend
             }
         }
@@ -150,13 +274,24 @@ pub async fn close_websocket(
     Ok(())
 }
 
+// Fills the request fields that come from the headers rather than from the body or the query.
+fn apply_request_headers(request_data: &mut ChessDataRequest, req: &HttpRequest) -> Locale {
+    let locale = localization::resolve_locale(request_data.locale, req);
+    request_data.locale = Some(locale);
+    request_data.lichess_token = LichessToken::from_request(req);
+    locale
+}
+
+// Every endpoint below fetches games from lichess. They authenticate with the token forwarded in
+// the Authorization header, or with the server-wide LICHESS_API_TOKEN when none is forwarded.
 #[post("/fetch-chess-data")]
 pub async fn fetch_chess_data(
-    info: web::Json<ChessDataRequest>,
+    mut info: web::Json<ChessDataRequest>,
     req: HttpRequest,
     app_state: web::Data<AppState>,
 ) -> impl Responder {
     let start_time = Instant::now();
+    let locale = apply_request_headers(&mut info, &req);
     let requested_by = RequestSource::from_str(
         req.headers()
             .get("x-requested-by")
@@ -167,7 +302,14 @@ pub async fn fetch_chess_data(
     let opt_websocket_addr = get_websocket_address(&requested_by, &app_state);
 
     // Fetch player data and send updates via WebSocket for accurate progression rate.
-    let fetch_result = lichess_client::fetch_player_data(&info, requested_by, &opt_websocket_addr).await;
+    let fetch_result = lichess_client::fetch_player_data(
+        &app_state.lichess_base_url,
+        &app_state.database_path,
+        &info,
+        requested_by,
+        &opt_websocket_addr,
+    )
+    .await;
 
     // TODO: Handle error. Close the WebSocket after processing all games.
     close_websocket(&opt_websocket_addr, &app_state).await.ok();
@@ -177,17 +319,371 @@ pub async fn fetch_chess_data(
             let end_time = Instant::now();
             let processing_time = end_time.duration_since(start_time).as_secs_f32();
 
-            match database::log_request_data(
+            // The timing log is best-effort, a finished analysis is returned regardless.
+            if let Err(error) = database::log_request_data(
+                &app_state.database_path,
                 info.games_count,
                 info.game_mode.as_str(),
                 info.user_color.as_str(),
                 info.user_elo,
                 processing_time,
             ) {
-                Ok(_) => response,
-                Err(_) => HttpResponse::InternalServerError().finish(),
+                log::warn!("The request data could not be logged: {}", error);
+            }
+            response
+        }
+        Err(e) => errors_manager::get_localized_error_response(&e, locale),
+    }
+}
+
+// Per-game analysis table, downloadable as CSV or NDJSON, e.g /export-chess-data?format=csv
+#[post("/export-chess-data")]
+pub async fn export_chess_data(
+    mut info: web::Json<ChessDataRequest>,
+    query: web::Query<ExportQuery>,
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let locale = apply_request_headers(&mut info, &req);
+    let export_result =
+        lichess_client::export_player_data(&app_state.lichess_base_url, &info, query.format).await;
+    match export_result {
+        Ok(response) => response,
+        Err(e) => errors_manager::get_localized_error_response(&e, locale),
+    }
+}
+
+// Self-contained HTML report with embedded SVG charts, meant to be shared or archived.
+#[post("/report-chess-data")]
+pub async fn report_chess_data(
+    mut info: web::Json<ChessDataRequest>,
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let locale = apply_request_headers(&mut info, &req);
+    match lichess_client::report_player_data(&app_state.lichess_base_url, &info).await {
+        Ok(response) => response,
+        Err(e) => errors_manager::get_localized_error_response(&e, locale),
+    }
+}
+
+// Charts are served through GET so that they can be embedded as images, e.g
+// /charts/trend?username=foo&games_count=50&game_mode=blitz&user_color=both&format=svg
+#[get("/charts/{chart_kind}")]
+pub async fn fetch_chess_chart(
+    chart_kind: web::Path<ChartKind>,
+    mut info: web::Query<ChessDataRequest>,
+    query: web::Query<ChartQuery>,
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let locale = apply_request_headers(&mut info, &req);
+    let chart_result = lichess_client::chart_player_data(
+        &app_state.lichess_base_url,
+        &info,
+        *chart_kind,
+        query.format,
+    )
+    .await;
+    match chart_result {
+        Ok(response) => response,
+        Err(e) => errors_manager::get_localized_error_response(&e, locale),
+    }
+}
+
+// Evolution of the analyses stored for a player, it does not fetch games from lichess, e.g
+// /progress/foo?game_mode=blitz&user_color=both&period_days=30
+#[get("/progress/{username}")]
+pub async fn fetch_progress(
+    username: web::Path<String>,
+    query: web::Query<ProgressQuery>,
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> impl Responder {
+    let locale = localization::resolve_locale(query.locale, &req);
+    match database::get_analysis_snapshots(
+        &app_state.database_path,
+        &username,
+        &query.get_perf_type(),
+    ) {
+        Ok(snapshots) => {
+            HttpResponse::Ok().json(progress_generator::generate(&username, &query, snapshots))
+        }
+        Err(_) => ProcessError::InternalError {
+            message: Message::DatabaseFailure,
+        }
+        .localized_error_response(locale),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TestDatabase;
+    use crate::mock_lichess_server::{MockGameLine, MockLichessServer, MockUser};
+    use crate::progress_generator::AnalysisSnapshot;
+    use crate::unit_test_util;
+
+    use actix_web::{http::StatusCode, test, App};
+    use futures::StreamExt;
+    use std::cell::Cell;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn get_app_state(lichess_base_url: &str, database: &TestDatabase) -> web::Data<AppState> {
+        web::Data::new(AppState {
+            websocket_session: Mutex::new(None),
+            lichess_base_url: lichess_base_url.to_string(),
+            database_path: database.path.clone(),
+        })
+    }
+
+    fn get_complete_games(created_ats: &[u64]) -> Vec<MockGameLine> {
+        created_ats
+            .iter()
+            .map(|created_at| {
+                MockGameLine::game(&unit_test_util::get_some_mocked_complete_game(*created_at))
+            })
+            .collect()
+    }
+
+    fn get_request_body(username: &str, games_count: i32) -> serde_json::Value {
+        serde_json::json!({
+            "username": username,
+            "games_count": games_count,
+            "game_mode": "blitz",
+            "user_color": "both",
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_chess_data() {
+        let mut games = get_complete_games(&[4000, 3000, 1000]);
+        games.insert(2, MockGameLine::malformed(2000));
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games,
+                truncate_after: Some(1),
+                ..Default::default()
+            },
+        )]);
+        let database = TestDatabase::create();
+        let app = test::init_service(
+            App::new()
+                .app_data(get_app_state(&mock_lichess.get_base_url(), &database))
+                .service(fetch_chess_data),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/fetch-chess-data")
+            .set_json(get_request_body("user", 4))
+            .to_request();
+        let response: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        // At half time, the user playing black has spent 11 more seconds than the opponent.
+        assert_eq!(response["time"], "-11");
+        assert_eq!(
+            response["games_with_errors"],
+            serde_json::json!([[3, "An internal error occured while processing this game."]])
+        );
+        // The games keep their position in the stream, the malformed one included.
+        let game_numbers = response["trend_chart_data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|datum| (datum["game_number"].clone(), datum["created_at"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            game_numbers,
+            [(1, 4000), (2, 3000), (4, 1000)].map(|(game_number, created_at)| (
+                serde_json::json!(game_number),
+                serde_json::json!(created_at)
+            ))
+        );
+
+        // The stream was truncated after the first game and resumed from it.
+        let requests = mock_lichess.get_requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].ends_with("max=4&until=4000"));
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_chess_data_unknown_user() {
+        let mock_lichess = MockLichessServer::start(Vec::new());
+        let database = TestDatabase::create();
+        let app = test::init_service(
+            App::new()
+                .app_data(get_app_state(&mock_lichess.get_base_url(), &database))
+                .service(fetch_chess_data),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/fetch-chess-data")
+            .insert_header(("Accept-Language", "fr"))
+            .set_json(get_request_body("nobody", 10))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(body["code"], "user_not_found");
+        assert_eq!(
+            body["error"],
+            "Aucun utilisateur lichess nommé nobody n'a été trouvé."
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_chess_data_without_database() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: get_complete_games(&[2000, 1000]),
+                ..Default::default()
+            },
+        )]);
+        // Neither the request log nor the snapshot can be written in a missing directory.
+        let database_path = std::env::temp_dir()
+            .join(uuid::Uuid::new_v4().to_string())
+            .join(database::DATABASE_PATH);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState {
+                    websocket_session: Mutex::new(None),
+                    lichess_base_url: mock_lichess.get_base_url(),
+                    database_path: database_path.to_string_lossy().into_owned(),
+                }))
+                .service(fetch_chess_data),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/fetch-chess-data")
+            .set_json(get_request_body("user", 2))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(body["trend_chart_data"].as_array().unwrap().len(), 2);
+        assert!(!database_path.exists());
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_chess_data_websocket_progress() {
+        let mock_lichess = MockLichessServer::start(vec![(
+            "user",
+            MockUser {
+                games: get_complete_games(&[3000, 2000, 1000]),
+                line_delay: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        )]);
+        let database = TestDatabase::create();
+        let app_state = get_app_state(&mock_lichess.get_base_url(), &database);
+        let mut server = actix_test::start(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .service(fetch_chess_data)
+                .service(
+                    web::resource("/ws").route(web::get().to(websocket::add_websocket_endpoint)),
+                )
+        });
+
+        let mut websocket = server.ws_at("/ws").await.unwrap();
+        let is_response_received = Cell::new(false);
+        let fetch_request = async {
+            let response = server
+                .post("/fetch-chess-data")
+                .send_json(&get_request_body("user", 3))
+                .await
+                .unwrap();
+            is_response_received.set(true);
+            response
+        };
+        let read_progress = async {
+            let mut frames = Vec::new();
+            while let Some(Ok(frame)) = websocket.next().await {
+                match frame {
+                    awc::ws::Frame::Text(text) => frames.push((
+                        String::from_utf8(text.to_vec()).unwrap(),
+                        is_response_received.get(),
+                    )),
+                    awc::ws::Frame::Close(_) => break,
+                    _ => {}
+                }
             }
+            frames
+        };
+        let (response, frames) = futures::join!(fetch_request, read_progress);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The slow stream guarantees that the progress is reported before the response.
+        assert!(!frames[0].1);
+        let messages = frames
+            .into_iter()
+            .map(|(message, _)| message)
+            .collect::<Vec<String>>();
+        assert_eq!(
+            messages,
+            [
+                "Game 1/3",
+                "Game 2/3",
+                "Game 3/3",
+                "All games processed. Closing connection."
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_progress() {
+        let database = TestDatabase::create();
+        let username = "user";
+        for (day, win_rate) in [(1, 0.4), (20, 0.5), (40, 0.6)] {
+            database::log_analysis_snapshot(
+                &database.path,
+                username,
+                &AnalysisSnapshot {
+                    created_at: day * 86_400_000,
+                    perf_type: String::from("blitz"),
+                    user_color: String::from("both"),
+                    games_count: 50,
+                    analysed_games_count: 40,
+                    average_time: Some(-10.0 + day as f32),
+                    win_rate,
+                    user_flag_rate: Some(0.125),
+                    opponent_flag_rate: Some(day as f32 / 40.0),
+                    consistency_score: None,
+                },
+            )
+            .unwrap();
         }
-        Err(e) => e.error_response(),
+        let app = test::init_service(
+            App::new()
+                .app_data(get_app_state(lichess_client::LICHESS_BASE_URL, &database))
+                .service(fetch_progress),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/progress/{}?game_mode=blitz", username.to_uppercase()))
+            .to_request();
+        let response: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response["period_in_days"], 30);
+        assert_eq!(response["snapshots"].as_array().unwrap().len(), 3);
+        assert_eq!(response["change"]["since"], 86_400_000);
+        assert_eq!(response["change"]["average_time"], 39.0);
+        assert_eq!(response["change"]["opponent_flag_rate"], 39.0 / 40.0);
+        assert_eq!(response["change"]["consistency_score"], serde_json::Value::Null);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/progress/{}?game_mode=bullet", username))
+            .to_request();
+        let response: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(response["snapshots"], serde_json::json!([]));
+        assert_eq!(response["change"], serde_json::Value::Null);
     }
 }
@@ -0,0 +1,258 @@
+use crate::games_info_generator::GameInfo;
+use crate::games_info_processor::{self, GamesSummary};
+use crate::service_intermediary::ChessDataRequest;
+use crate::util;
+
+use serde::Serialize;
+
+const DEFAULT_SESSION_GAP_IN_MINUTES: u64 = 30;
+const MILLISECONDS_PER_MINUTE: u64 = 60_000;
+
+// Tilt heuristics: games played right after this many consecutive losses of the same session,
+// and games played after this many games of the same session.
+const MIN_CONSECUTIVE_LOSSES_FOR_TILT: usize = 2;
+const LONG_SESSION_GAMES_COUNT: usize = 5;
+
+// Gap between the compared groups to raise the speed up flag, the other thresholds are shared
+// with the other comparisons, see util::MIN_GAMES_FOR_COMPARISON.
+const SIGNIFICANT_SPEED_UP_IN_SECONDS: f32 = 5.0;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SessionSummary {
+    pub session_number: usize, // Chronological, starting at 1
+    pub started_at: u64,       // Unix timestamp in milliseconds
+    pub ended_at: u64,         // Unix timestamp in milliseconds
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+    #[serde(flatten)]
+    pub summary: GamesSummary,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TiltAnalysis {
+    pub after_consecutive_losses: GamesSummary,
+    pub other_games: GamesSummary,
+    pub early_in_long_sessions: GamesSummary,
+    pub late_in_long_sessions: GamesSummary,
+    // Raised when the half time differential increases after consecutive losses, i.e the
+    // user plays faster than usual.
+    pub speeds_up_after_losses: bool,
+    pub results_degrade_after_losses: bool,
+    pub results_degrade_in_long_sessions: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SessionsAnalysis {
+    pub session_gap_in_minutes: u64,
+    pub sessions: Vec<SessionSummary>,
+    pub tilt: TiltAnalysis,
+}
+
+pub fn get_session_gap_in_minutes(request_data: &ChessDataRequest) -> u64 {
+    request_data
+        .session_gap_minutes
+        .unwrap_or(DEFAULT_SESSION_GAP_IN_MINUTES)
+}
+
+fn is_loss(game_info: &GameInfo) -> bool {
+    !util::is_game_draw(game_info) && !util::has_user_won_game(game_info)
+}
+
+fn get_game_end(game_info: &GameInfo) -> Option<u64> {
+    game_info.last_move_at.or(game_info.created_at)
+}
+
+// Sorts the games chronologically and splits them whenever the time between the end of a
+// game and the start of the next one exceeds the session gap. Games without a timestamp
+// cannot be placed in a session.
+fn split_into_sessions<'a>(
+    games_with_differentials: &[(&'a GameInfo, f32)],
+    session_gap_in_minutes: u64,
+) -> Vec<Vec<(&'a GameInfo, f32)>> {
+    let mut timed_games = games_with_differentials
+        .iter()
+        .filter(|(game_info, _)| game_info.created_at.is_some())
+        .copied()
+        .collect::<Vec<(&GameInfo, f32)>>();
+    timed_games.sort_by_key(|(game_info, _)| game_info.created_at);
+
+    let session_gap = session_gap_in_minutes * MILLISECONDS_PER_MINUTE;
+    let mut sessions: Vec<Vec<(&GameInfo, f32)>> = Vec::new();
+    for game in timed_games {
+        let continues_session = sessions
+            .last()
+            .and_then(|session| session.last())
+            .and_then(|(previous_game, _)| get_game_end(previous_game))
+            .zip(game.0.created_at)
+            .is_some_and(|(previous_end, start)| start.saturating_sub(previous_end) <= session_gap);
+
+        match sessions.last_mut() {
+            Some(session) if continues_session => session.push(game),
+            _ => sessions.push(vec![game]),
+        }
+    }
+
+    sessions
+}
+
+fn get_longest_streaks(session: &[(&GameInfo, f32)]) -> (usize, usize) {
+    let (mut longest_win_streak, mut longest_loss_streak) = (0, 0);
+    let (mut win_streak, mut loss_streak) = (0, 0);
+
+    for (game_info, _) in session {
+        win_streak = if util::has_user_won_game(game_info) {
+            win_streak + 1
+        } else {
+            0
+        };
+        loss_streak = if is_loss(game_info) {
+            loss_streak + 1
+        } else {
+            0
+        };
+        longest_win_streak = usize::max(longest_win_streak, win_streak);
+        longest_loss_streak = usize::max(longest_loss_streak, loss_streak);
+    }
+
+    (longest_win_streak, longest_loss_streak)
+}
+
+fn summarize_session(session_number: usize, session: &[(&GameInfo, f32)]) -> SessionSummary {
+    let (longest_win_streak, longest_loss_streak) = get_longest_streaks(session);
+    let (first_game, last_game) = (session[0].0, session[session.len() - 1].0);
+
+    SessionSummary {
+        session_number,
+        started_at: first_game.created_at.unwrap_or(0),
+        ended_at: get_game_end(last_game).unwrap_or(0),
+        longest_win_streak,
+        longest_loss_streak,
+        summary: games_info_processor::summarize_games(session),
+    }
+}
+
+fn analyse_tilt(sessions: &[Vec<(&GameInfo, f32)>]) -> TiltAnalysis {
+    let mut after_consecutive_losses = Vec::new();
+    let mut other_games = Vec::new();
+    let mut early_in_long_sessions = Vec::new();
+    let mut late_in_long_sessions = Vec::new();
+
+    for session in sessions {
+        let mut loss_streak = 0;
+        for (i, game) in session.iter().enumerate() {
+            if loss_streak >= MIN_CONSECUTIVE_LOSSES_FOR_TILT {
+                after_consecutive_losses.push(*game);
+            } else {
+                other_games.push(*game);
+            }
+            loss_streak = if is_loss(game.0) { loss_streak + 1 } else { 0 };
+
+            if session.len() > LONG_SESSION_GAMES_COUNT {
+                if i < LONG_SESSION_GAMES_COUNT {
+                    early_in_long_sessions.push(*game);
+                } else {
+                    late_in_long_sessions.push(*game);
+                }
+            }
+        }
+    }
+
+    let after_consecutive_losses = games_info_processor::summarize_games(&after_consecutive_losses);
+    let other_games = games_info_processor::summarize_games(&other_games);
+    let early_in_long_sessions = games_info_processor::summarize_games(&early_in_long_sessions);
+    let late_in_long_sessions = games_info_processor::summarize_games(&late_in_long_sessions);
+
+    let speeds_up_after_losses = after_consecutive_losses.games_count
+        >= util::MIN_GAMES_FOR_COMPARISON
+        && other_games.games_count >= util::MIN_GAMES_FOR_COMPARISON
+        && after_consecutive_losses
+            .average_time_differential
+            .zip(other_games.average_time_differential)
+            .is_some_and(|(after_losses_time, other_time)| {
+                after_losses_time - other_time >= SIGNIFICANT_SPEED_UP_IN_SECONDS
+            });
+
+    TiltAnalysis {
+        speeds_up_after_losses,
+        results_degrade_after_losses: util::is_significantly_lower(
+            &after_consecutive_losses,
+            &other_games,
+        ),
+        results_degrade_in_long_sessions: util::is_significantly_lower(
+            &late_in_long_sessions,
+            &early_in_long_sessions,
+        ),
+        after_consecutive_losses,
+        other_games,
+        early_in_long_sessions,
+        late_in_long_sessions,
+    }
+}
+
+pub fn generate(
+    games_with_differentials: &[(&GameInfo, f32)],
+    session_gap_in_minutes: u64,
+) -> SessionsAnalysis {
+    let sessions = split_into_sessions(games_with_differentials, session_gap_in_minutes);
+
+    SessionsAnalysis {
+        session_gap_in_minutes,
+        sessions: sessions
+            .iter()
+            .enumerate()
+            .map(|(i, session)| summarize_session(i + 1, session))
+            .collect(),
+        tilt: analyse_tilt(&sessions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    // Builds a game from the mocked game a (lost by the user) starting `start_minute`
+    // minutes after the mocked timestamp and lasting 5 minutes.
+    fn make_game(start_minute: u64, has_user_won: bool) -> GameInfo {
+        let mut game_info =
+            games_info_generator::generate(&unit_test_util::get_some_mocked_game_a(), &0, "user")
+                .unwrap();
+        let start = 1672371185802 + start_minute * MILLISECONDS_PER_MINUTE;
+        game_info.created_at = Some(start);
+        game_info.last_move_at = Some(start + 5 * MILLISECONDS_PER_MINUTE);
+        if has_user_won {
+            game_info.winner_color = Some(game_info.user_color.clone());
+        }
+        game_info
+    }
+
+    #[test]
+    fn test_generate() {
+        // Lichess streams the most recent game first. Two sessions: minutes 0 to 25 and 200.
+        let games = [
+            make_game(200, true),
+            make_game(20, false),
+            make_game(10, false),
+            make_game(0, true),
+        ];
+        let games_with_differentials = games
+            .iter()
+            .map(|game_info| (game_info, 1.0))
+            .collect::<Vec<(&GameInfo, f32)>>();
+
+        let analysis = generate(&games_with_differentials, 30);
+        assert_eq!(analysis.sessions.len(), 2);
+
+        let first_session = &analysis.sessions[0];
+        assert_eq!(first_session.session_number, 1);
+        assert_eq!(first_session.summary.games_count, 3);
+        assert_eq!(first_session.longest_win_streak, 1);
+        assert_eq!(first_session.longest_loss_streak, 2);
+        assert_eq!(first_session.ended_at, games[1].last_move_at.unwrap());
+
+        // The game following the two losses starts a new session, so it does not count.
+        assert_eq!(analysis.tilt.after_consecutive_losses.games_count, 0);
+        assert_eq!(analysis.tilt.other_games.games_count, 4);
+        assert!(!analysis.tilt.speeds_up_after_losses);
+    }
+}
@@ -0,0 +1,455 @@
+use crate::clock_curve_generator::ClockCurvePoint;
+use crate::errors_manager::ProcessError;
+use crate::localization::{Locale, Message};
+use crate::trend_chart_generator::TrendChartDatum;
+
+use std::f32::consts::PI;
+use std::fmt::Write;
+
+// Colors are kept in sync with the frontend ApexCharts configuration.
+const TREND_LINE_COLOR: &str = "#1A56DB";
+const WIN_COLOR: &str = "#10B981";
+const LOSS_COLOR: &str = "#EF4444";
+const DRAW_COLOR: &str = "#9CA3AF";
+const USER_FLAG_COLOR: &str = "#f87171";
+const OPPONENT_FLAG_COLOR: &str = "#4ade80";
+const OPPONENT_CLOCK_COLOR: &str = "#6B7280";
+const ROLLING_MEAN_COLOR: &str = "#F59E0B";
+const AXIS_COLOR: &str = "#9CA3AF";
+const FONT_FAMILY: &str = "Inter, sans-serif";
+
+const TREND_CHART_WIDTH: f32 = 640.0;
+const TREND_CHART_HEIGHT: f32 = 300.0;
+const TREND_CHART_PADDING: f32 = 40.0;
+
+const DONUT_CHART_SIZE: f32 = 240.0;
+const DONUT_OUTER_RADIUS: f32 = 100.0;
+const DONUT_INNER_RADIUS: f32 = 65.0;
+
+const FLAG_CHART_WIDTH: f32 = 480.0;
+const FLAG_CHART_BAR_HEIGHT: f32 = 28.0;
+const FLAG_CHART_LABEL_WIDTH: f32 = 150.0;
+
+fn open_svg(svg: &mut String, width: f32, height: f32) -> Result<(), ProcessError> {
+    write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"{FONT_FAMILY}\" font-size=\"12\">",
+        w = width,
+        h = height
+    )?;
+    Ok(())
+}
+
+fn get_value_range(values: &[f32]) -> (f32, f32) {
+    // Always include the zero line so that the sign of the differential is readable.
+    let min = values.iter().cloned().fold(0.0_f32, f32::min);
+    let max = values.iter().cloned().fold(0.0_f32, f32::max);
+    if (max - min).abs() < f32::EPSILON {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    }
+}
+
+pub fn generate_trend_chart(
+    trend_chart_data: &[TrendChartDatum],
+    locale: Locale,
+) -> Result<String, ProcessError> {
+    let mut svg = String::new();
+    open_svg(&mut svg, TREND_CHART_WIDTH, TREND_CHART_HEIGHT)?;
+
+    let values = trend_chart_data
+        .iter()
+        .map(|datum| datum.time_differential)
+        .collect::<Vec<f32>>();
+    let (min, max) = get_value_range(&values);
+
+    let plot_width = TREND_CHART_WIDTH - 2.0 * TREND_CHART_PADDING;
+    let plot_height = TREND_CHART_HEIGHT - 2.0 * TREND_CHART_PADDING;
+    let x_step = plot_width / (values.len().max(2) - 1) as f32;
+    let to_x = |i: usize| TREND_CHART_PADDING + i as f32 * x_step;
+    let to_y = |value: f32| TREND_CHART_PADDING + (max - value) / (max - min) * plot_height;
+
+    // Zero line and axis labels
+    write!(
+        svg,
+        "<line x1=\"{x1:.1}\" y1=\"{y:.1}\" x2=\"{x2:.1}\" y2=\"{y:.1}\" stroke=\"{AXIS_COLOR}\" \
+         stroke-dasharray=\"4 4\"/>",
+        x1 = TREND_CHART_PADDING,
+        x2 = TREND_CHART_WIDTH - TREND_CHART_PADDING,
+        y = to_y(0.0)
+    )?;
+    write!(
+        svg,
+        "<text x=\"4\" y=\"{:.1}\" fill=\"{AXIS_COLOR}\">{:+.0}s</text>\
+         <text x=\"4\" y=\"{:.1}\" fill=\"{AXIS_COLOR}\">{:+.0}s</text>",
+        TREND_CHART_PADDING,
+        max,
+        TREND_CHART_HEIGHT - TREND_CHART_PADDING,
+        min
+    )?;
+
+    if !values.is_empty() {
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("{:.1},{:.1}", to_x(i), to_y(*value)))
+            .collect::<Vec<String>>()
+            .join(" ");
+        write!(
+            svg,
+            "<polyline points=\"{points}\" fill=\"none\" stroke=\"{TREND_LINE_COLOR}\" \
+             stroke-width=\"2\"/>"
+        )?;
+    }
+
+    // The rolling mean is only defined once the window is filled, draw the defined part.
+    let rolling_mean_points = trend_chart_data
+        .iter()
+        .enumerate()
+        .filter_map(|(i, datum)| {
+            datum
+                .rolling_mean
+                .map(|mean| format!("{:.1},{:.1}", to_x(i), to_y(mean)))
+        })
+        .collect::<Vec<String>>();
+    if rolling_mean_points.len() >= 2 {
+        write!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{ROLLING_MEAN_COLOR}\" \
+             stroke-width=\"2\" stroke-dasharray=\"6 3\"/>",
+            rolling_mean_points.join(" ")
+        )?;
+    }
+
+    for (i, datum) in trend_chart_data.iter().enumerate() {
+        let color = match datum.win_status.as_str() {
+            "win" => WIN_COLOR,
+            "draw" => DRAW_COLOR,
+            _ => LOSS_COLOR,
+        };
+        write!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{color}\">\
+             <title>{}: {:+.2}s ({})</title></circle>",
+            to_x(i),
+            to_y(datum.time_differential),
+            Message::GameNumber {
+                game_number: datum.game_number
+            }
+            .localize(locale),
+            datum.time_differential,
+            Message::GameResult {
+                win_status: datum.win_status.clone()
+            }
+            .localize(locale)
+        )?;
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+fn get_arc_path(start_angle: f32, end_angle: f32) -> String {
+    let center = DONUT_CHART_SIZE / 2.0;
+    let point =
+        |radius: f32, angle: f32| (center + radius * angle.sin(), center - radius * angle.cos());
+    let large_arc_flag = if end_angle - start_angle > PI { 1 } else { 0 };
+
+    let (outer_start_x, outer_start_y) = point(DONUT_OUTER_RADIUS, start_angle);
+    let (outer_end_x, outer_end_y) = point(DONUT_OUTER_RADIUS, end_angle);
+    let (inner_end_x, inner_end_y) = point(DONUT_INNER_RADIUS, end_angle);
+    let (inner_start_x, inner_start_y) = point(DONUT_INNER_RADIUS, start_angle);
+
+    format!(
+        "M {:.2} {:.2} A {o} {o} 0 {l} 1 {:.2} {:.2} L {:.2} {:.2} A {i} {i} 0 {l} 0 {:.2} {:.2} Z",
+        outer_start_x,
+        outer_start_y,
+        outer_end_x,
+        outer_end_y,
+        inner_end_x,
+        inner_end_y,
+        inner_start_x,
+        inner_start_y,
+        o = DONUT_OUTER_RADIUS,
+        i = DONUT_INNER_RADIUS,
+        l = large_arc_flag
+    )
+}
+
+pub fn generate_win_rate_donut_chart(win_rate: f32) -> Result<String, ProcessError> {
+    let mut svg = String::new();
+    let win_rate = win_rate.clamp(0.0, 1.0);
+    let center = DONUT_CHART_SIZE / 2.0;
+    open_svg(&mut svg, DONUT_CHART_SIZE, DONUT_CHART_SIZE)?;
+
+    // A full circle cannot be drawn with a single arc, so 0% and 100% are rendered as rings.
+    let ring_width = DONUT_OUTER_RADIUS - DONUT_INNER_RADIUS;
+    let ring_radius = (DONUT_OUTER_RADIUS + DONUT_INNER_RADIUS) / 2.0;
+    if win_rate <= 0.0 || win_rate >= 1.0 {
+        let color = if win_rate >= 1.0 {
+            WIN_COLOR
+        } else {
+            LOSS_COLOR
+        };
+        write!(
+            svg,
+            "<circle cx=\"{center}\" cy=\"{center}\" r=\"{ring_radius}\" fill=\"none\" \
+             stroke=\"{color}\" stroke-width=\"{ring_width}\"/>"
+        )?;
+    } else {
+        let win_angle = 2.0 * PI * win_rate;
+        write!(
+            svg,
+            "<path d=\"{}\" fill=\"{WIN_COLOR}\"/><path d=\"{}\" fill=\"{LOSS_COLOR}\"/>",
+            get_arc_path(0.0, win_angle),
+            get_arc_path(win_angle, 2.0 * PI)
+        )?;
+    }
+
+    write!(
+        svg,
+        "<text x=\"{center}\" y=\"{center}\" text-anchor=\"middle\" dominant-baseline=\"middle\" \
+         font-size=\"24\" font-weight=\"bold\">{:.0}%</text>",
+        win_rate * 100.0
+    )?;
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+pub fn generate_flag_counts_chart(
+    players_flag_counts: (i32, i32),
+    total_games_considered: usize,
+    locale: Locale,
+) -> Result<String, ProcessError> {
+    let mut svg = String::new();
+    let bars = [
+        (
+            Message::ChartUserFlagged,
+            players_flag_counts.0,
+            USER_FLAG_COLOR,
+        ),
+        (
+            Message::ChartOpponentsFlagged,
+            players_flag_counts.1,
+            OPPONENT_FLAG_COLOR,
+        ),
+        (
+            Message::ChartGamesConsidered,
+            total_games_considered as i32,
+            OPPONENT_FLAG_COLOR,
+        ),
+    ];
+    let height = FLAG_CHART_BAR_HEIGHT * 2.0 * bars.len() as f32;
+    open_svg(&mut svg, FLAG_CHART_WIDTH, height)?;
+
+    let max_count = bars.iter().map(|bar| bar.1).max().unwrap_or(0).max(1) as f32;
+    let max_bar_width = FLAG_CHART_WIDTH - FLAG_CHART_LABEL_WIDTH - 40.0;
+
+    for (i, (label, count, color)) in bars.iter().enumerate() {
+        let y = FLAG_CHART_BAR_HEIGHT * (2.0 * i as f32 + 0.5);
+        let bar_width = max_bar_width * (*count).max(0) as f32 / max_count;
+        write!(
+            svg,
+            "<text x=\"0\" y=\"{:.1}\" dominant-baseline=\"middle\">{}</text>\
+             <rect x=\"{FLAG_CHART_LABEL_WIDTH}\" y=\"{y:.1}\" width=\"{bar_width:.1}\" \
+             height=\"{FLAG_CHART_BAR_HEIGHT}\" fill=\"{color}\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" dominant-baseline=\"middle\">{count}</text>",
+            y + FLAG_CHART_BAR_HEIGHT / 2.0,
+            label.localize(locale),
+            FLAG_CHART_LABEL_WIDTH + bar_width + 6.0,
+            y + FLAG_CHART_BAR_HEIGHT / 2.0
+        )?;
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+pub fn generate_clock_curve_chart(
+    clock_curve: &[ClockCurvePoint],
+    locale: Locale,
+) -> Result<String, ProcessError> {
+    let mut svg = String::new();
+    open_svg(&mut svg, TREND_CHART_WIDTH, TREND_CHART_HEIGHT)?;
+
+    let max_clock = clock_curve
+        .iter()
+        .map(|point| point.user_clock.max(point.opponent_clock))
+        .fold(1.0_f32, f32::max);
+
+    let plot_width = TREND_CHART_WIDTH - 2.0 * TREND_CHART_PADDING;
+    let plot_height = TREND_CHART_HEIGHT - 2.0 * TREND_CHART_PADDING;
+    let x_step = plot_width / (clock_curve.len().max(2) - 1) as f32;
+    let to_x = |i: usize| TREND_CHART_PADDING + i as f32 * x_step;
+    let to_y = |clock: f32| TREND_CHART_PADDING + (max_clock - clock) / max_clock * plot_height;
+
+    write!(
+        svg,
+        "<text x=\"4\" y=\"{:.1}\" fill=\"{AXIS_COLOR}\">{:.0}s</text>\
+         <text x=\"4\" y=\"{:.1}\" fill=\"{AXIS_COLOR}\">0s</text>\
+         <text x=\"{:.1}\" y=\"{:.1}\" fill=\"{AXIS_COLOR}\" text-anchor=\"end\">{}</text>",
+        TREND_CHART_PADDING,
+        max_clock,
+        TREND_CHART_HEIGHT - TREND_CHART_PADDING,
+        TREND_CHART_WIDTH - TREND_CHART_PADDING,
+        TREND_CHART_HEIGHT - TREND_CHART_PADDING / 2.0,
+        Message::ChartMoveNumber {
+            move_number: clock_curve.len()
+        }
+        .localize(locale)
+    )?;
+
+    let series = [
+        (
+            Message::ChartUser,
+            TREND_LINE_COLOR,
+            clock_curve
+                .iter()
+                .map(|point| point.user_clock)
+                .collect::<Vec<f32>>(),
+        ),
+        (
+            Message::ChartOpponent,
+            OPPONENT_CLOCK_COLOR,
+            clock_curve
+                .iter()
+                .map(|point| point.opponent_clock)
+                .collect::<Vec<f32>>(),
+        ),
+    ];
+
+    for (i, (label, color, clocks)) in series.iter().enumerate() {
+        if !clocks.is_empty() {
+            let points = clocks
+                .iter()
+                .enumerate()
+                .map(|(j, clock)| format!("{:.1},{:.1}", to_x(j), to_y(*clock)))
+                .collect::<Vec<String>>()
+                .join(" ");
+            write!(
+                svg,
+                "<polyline points=\"{points}\" fill=\"none\" stroke=\"{color}\" \
+                 stroke-width=\"2\"/>"
+            )?;
+        }
+
+        // Legend
+        let legend_x = TREND_CHART_WIDTH - TREND_CHART_PADDING - 160.0 + i as f32 * 80.0;
+        write!(
+            svg,
+            "<rect x=\"{legend_x:.1}\" y=\"8\" width=\"12\" height=\"12\" fill=\"{color}\"/>\
+             <text x=\"{:.1}\" y=\"18\">{}</text>",
+            legend_x + 16.0,
+            label.localize(locale)
+        )?;
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+#[cfg(feature = "png")]
+pub fn rasterize_to_png(svg: &str) -> Result<Vec<u8>, ProcessError> {
+    use resvg::{tiny_skia, usvg};
+
+    let rasterization_error = |details: String| ProcessError::InternalError {
+        message: Message::ChartRasterizationFailed { details },
+    };
+
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+
+    let tree = usvg::Tree::from_str(svg, &options)
+        .map_err(|e| rasterization_error(format!("Invalid SVG chart: {}", e)))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| rasterization_error(String::from("Invalid SVG chart size.")))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| rasterization_error(format!("PNG encoding failed: {}", e)))
+}
+
+#[cfg(not(feature = "png"))]
+pub fn rasterize_to_png(_: &str) -> Result<Vec<u8>, ProcessError> {
+    Err(ProcessError::DataError {
+        message: Message::PngChartsDisabled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_trend_chart() {
+        let trend_chart_data = [(2.0, "win"), (-4.0, "loss"), (0.0, "draw")]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (time_differential, win_status))| TrendChartDatum {
+                time_differential,
+                win_status: win_status.to_string(),
+                game_number: i as i32 + 1,
+                created_at: None,
+                user_rating: 1500,
+                rating_diff: None,
+                rolling_mean: (i > 0).then_some(-1.0),
+                rolling_median: None,
+                exponential_average: 0.0,
+            })
+            .collect::<Vec<TrendChartDatum>>();
+
+        let svg = generate_trend_chart(&trend_chart_data, Locale::En).unwrap();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert!(svg.contains(&format!("stroke=\"{TREND_LINE_COLOR}\"")));
+        assert!(svg.contains(&format!("stroke=\"{ROLLING_MEAN_COLOR}\"")));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(svg.contains(&format!("fill=\"{WIN_COLOR}\"><title>Game 1: +2.00s (")));
+        assert!(svg.contains(&format!("fill=\"{LOSS_COLOR}\"><title>Game 2: -4.00s (")));
+        assert!(svg.contains(&format!("fill=\"{DRAW_COLOR}\"><title>Game 3: +0.00s (")));
+        assert!(svg.contains(">+2s</text>"));
+        assert!(svg.contains(">-4s</text>"));
+    }
+
+    #[test]
+    fn test_generate_clock_curve_chart() {
+        let clock_curve = (1..=3)
+            .map(|move_number| ClockCurvePoint {
+                move_number,
+                user_clock: 180.0 - move_number as f32,
+                opponent_clock: 180.0,
+                games_count: 1,
+            })
+            .collect::<Vec<ClockCurvePoint>>();
+
+        let svg = generate_clock_curve_chart(&clock_curve, Locale::Es).unwrap();
+        assert!(svg.contains(">jugada 3</text>"));
+        assert!(svg.contains(">Jugador</text>"));
+        assert!(svg.contains(">Rival</text>"));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_rasterize_to_png() {
+        let svg = generate_win_rate_donut_chart(0.6).unwrap();
+        let png = rasterize_to_png(&svg).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[cfg(not(feature = "png"))]
+    #[test]
+    fn test_rasterize_to_png() {
+        let svg = generate_win_rate_donut_chart(0.6).unwrap();
+        assert!(matches!(
+            rasterize_to_png(&svg),
+            Err(ProcessError::DataError {
+                message: Message::PngChartsDisabled
+            })
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::errors_manager::ProcessError;
+use crate::games_info_generator::GameInfo;
+use crate::games_info_processor::{self, GamesSummary};
+use crate::localization::Message;
+use crate::service_intermediary::ChessDataRequest;
+
+use chrono::{DateTime, Datelike, Timelike, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct HourOfDayBucket {
+    pub hour: u32, // In [0, 23], local to the requested timezone
+    #[serde(flatten)]
+    pub summary: GamesSummary,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct WeekdayBucket {
+    pub weekday: String, // "Monday" to "Sunday", local to the requested timezone
+    #[serde(flatten)]
+    pub summary: GamesSummary,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TimeOfDayAnalysis {
+    pub timezone: String,
+    pub hours_of_day: Vec<HourOfDayBucket>,
+    pub weekdays: Vec<WeekdayBucket>,
+}
+
+// The timezone is an IANA name such as "Europe/Paris". Games are bucketed in UTC by default.
+pub fn get_timezone(request_data: &ChessDataRequest) -> Result<Tz, ProcessError> {
+    match &request_data.timezone {
+        Some(timezone) => timezone.parse::<Tz>().map_err(|_| ProcessError::DataError {
+            message: Message::UnknownTimezone {
+                timezone: timezone.clone(),
+            },
+        }),
+        None => Ok(Tz::UTC),
+    }
+}
+
+fn get_local_datetime(game_info: &GameInfo, timezone: &Tz) -> Option<DateTime<Tz>> {
+    let created_at = DateTime::from_timestamp_millis(game_info.created_at? as i64)?;
+    Some(created_at.with_timezone(timezone))
+}
+
+fn get_weekday_name(weekday: Weekday) -> String {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+    .to_string()
+}
+
+// Games without a creation date are left out of both breakdowns.
+pub fn generate(games_with_differentials: &[(&GameInfo, f32)], timezone: Tz) -> TimeOfDayAnalysis {
+    let local_games = games_with_differentials
+        .iter()
+        .filter_map(|game| {
+            get_local_datetime(game.0, &timezone).map(|local_datetime| (local_datetime, *game))
+        })
+        .collect::<Vec<(DateTime<Tz>, (&GameInfo, f32))>>();
+
+    let summarize_games_matching = |predicate: &dyn Fn(&DateTime<Tz>) -> bool| {
+        let matching_games = local_games
+            .iter()
+            .filter(|(local_datetime, _)| predicate(local_datetime))
+            .map(|(_, game)| *game)
+            .collect::<Vec<(&GameInfo, f32)>>();
+        games_info_processor::summarize_games(&matching_games)
+    };
+
+    TimeOfDayAnalysis {
+        timezone: timezone.name().to_string(),
+        hours_of_day: (0..24)
+            .map(|hour| HourOfDayBucket {
+                hour,
+                summary: summarize_games_matching(&|local_datetime| local_datetime.hour() == hour),
+            })
+            .collect(),
+        weekdays: WEEKDAYS
+            .iter()
+            .map(|weekday| WeekdayBucket {
+                weekday: get_weekday_name(*weekday),
+                summary: summarize_games_matching(&|local_datetime| {
+                    local_datetime.weekday() == *weekday
+                }),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate() {
+        // Game a was created on Friday 2022-12-30 at 03:33 UTC, i.e 22:33 on Thursday in New York.
+        let game_a = unit_test_util::get_some_mocked_game_a();
+        let input_games = [games_info_generator::generate(&game_a, &0, "user").unwrap()];
+        let games_with_differentials = vec![(&input_games[0], -0.08)];
+
+        let analysis = generate(&games_with_differentials, Tz::UTC);
+        assert_eq!(analysis.hours_of_day.len(), 24);
+        assert_eq!(analysis.hours_of_day[3].summary.games_count, 1);
+        assert_eq!(analysis.weekdays[4].weekday, "Friday");
+        assert_eq!(analysis.weekdays[4].summary.games_count, 1);
+
+        let request_data = ChessDataRequest {
+            timezone: Some("America/New_York".to_string()),
+            ..Default::default()
+        };
+        let timezone = get_timezone(&request_data).unwrap();
+        let analysis = generate(&games_with_differentials, timezone);
+        assert_eq!(analysis.timezone, "America/New_York");
+        assert_eq!(analysis.hours_of_day[22].summary.games_count, 1);
+        assert_eq!(analysis.weekdays[3].summary.games_count, 1);
+        assert_eq!(analysis.weekdays[4].summary, GamesSummary::default());
+
+        let request_data = ChessDataRequest {
+            timezone: Some("Mars/Olympus_Mons".to_string()),
+            ..Default::default()
+        };
+        assert!(get_timezone(&request_data).is_err());
+    }
+}
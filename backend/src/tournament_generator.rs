@@ -0,0 +1,90 @@
+use crate::games_info_generator::GameInfo;
+use crate::games_info_processor::{self, GamesSummary};
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+// Arena games are played under a different pressure, e.g berserk and the points streaks, so
+// they are summarized apart from the other games.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TournamentAnalysis {
+    pub tournaments_count: usize,
+    pub tournament_games: GamesSummary,
+    pub berserked_games: GamesSummary, // Tournament games in which the user berserked
+    pub opponent_berserked_games: GamesSummary,
+    pub other_games: GamesSummary,
+}
+
+fn summarize_games_matching(
+    games_with_differentials: &[(&GameInfo, f32)],
+    predicate: impl Fn(&GameInfo) -> bool,
+) -> GamesSummary {
+    let games = games_with_differentials
+        .iter()
+        .filter(|(game_info, _)| predicate(game_info))
+        .copied()
+        .collect::<Vec<(&GameInfo, f32)>>();
+    games_info_processor::summarize_games(&games)
+}
+
+pub fn generate(games_with_differentials: &[(&GameInfo, f32)]) -> Option<TournamentAnalysis> {
+    let tournament_ids = games_with_differentials
+        .iter()
+        .filter_map(|(game_info, _)| game_info.tournament_id.as_deref())
+        .collect::<HashSet<&str>>();
+    if tournament_ids.is_empty() {
+        return None;
+    }
+
+    Some(TournamentAnalysis {
+        tournaments_count: tournament_ids.len(),
+        tournament_games: summarize_games_matching(games_with_differentials, |game_info| {
+            game_info.tournament_id.is_some()
+        }),
+        berserked_games: summarize_games_matching(games_with_differentials, |game_info| {
+            game_info.user_berserked
+        }),
+        opponent_berserked_games: summarize_games_matching(games_with_differentials, |game_info| {
+            game_info.opponent_berserked
+        }),
+        other_games: summarize_games_matching(games_with_differentials, |game_info| {
+            game_info.tournament_id.is_none()
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{games_info_generator, unit_test_util};
+
+    #[test]
+    fn test_generate() {
+        let game = unit_test_util::get_some_mocked_complete_game(1000);
+        let mut games = (0..4)
+            .map(|game_idx| games_info_generator::generate(&game, &game_idx, "user").unwrap())
+            .collect::<Vec<GameInfo>>();
+        let games_with_differentials = games
+            .iter()
+            .map(|game_info| (game_info, -1.0))
+            .collect::<Vec<(&GameInfo, f32)>>();
+        assert_eq!(generate(&games_with_differentials), None);
+
+        games[0].tournament_id = Some(String::from("arena1"));
+        games[0].user_berserked = true;
+        games[1].tournament_id = Some(String::from("arena1"));
+        games[2].tournament_id = Some(String::from("arena2"));
+        games[2].opponent_berserked = true;
+        let games_with_differentials = games
+            .iter()
+            .map(|game_info| (game_info, -1.0))
+            .collect::<Vec<(&GameInfo, f32)>>();
+
+        let analysis = generate(&games_with_differentials).unwrap();
+        assert_eq!(analysis.tournaments_count, 2);
+        assert_eq!(analysis.tournament_games.games_count, 3);
+        assert_eq!(analysis.berserked_games.games_count, 1);
+        assert_eq!(analysis.opponent_berserked_games.games_count, 1);
+        assert_eq!(analysis.other_games.games_count, 1);
+    }
+}
@@ -1,55 +1,331 @@
 use crate::games_info_generator::GameInfo;
-use crate::service_intermediary::GameFetchWarning;
+use crate::service_intermediary::{ChessDataRequest, GameFetchWarning, TrendChartBucketSize};
 use crate::util;
 
 use serde::Serialize;
 use std::collections::HashMap;
 
+const DEFAULT_ROLLING_WINDOW: usize = 5;
+const DEFAULT_EXPONENTIAL_SMOOTHING_FACTOR: f32 = 0.3;
+
 #[derive(Serialize, Debug, PartialEq)]
 pub struct TrendChartDatum {
     pub time_differential: f32,
     pub win_status: String,
     pub game_number: i32,
+    pub created_at: Option<u64>, // Unix timestamp in milliseconds
+    pub user_rating: i32,        // Rating before the game
+    pub rating_diff: Option<i32>,
+    // Smoothed values over the games played up to this one (chronologically). The rolling
+    // values are None until enough games are available to fill the window.
+    pub rolling_mean: Option<f32>,
+    pub rolling_median: Option<f32>,
+    pub exponential_average: f32,
+}
+
+// Least squares fit of the time differential against the chronological game order.
+// A positive slope means that the user is getting better at managing their time.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TrendLine {
+    pub slope: f32,     // seconds per game
+    pub intercept: f32, // seconds, at the oldest game of the sample
+}
+
+// Aggregation of the trend chart over a calendar period (UTC), for long game histories.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TrendChartBucket {
+    pub bucket_start: String,        // First day of the period, YYYY-MM-DD
+    pub bucket_start_timestamp: u64, // Unix timestamp in milliseconds
+    pub games_count: usize,
+    pub average_time_differential: f32, // seconds
+    pub win_rate: Option<f32>,          // None without decisive games, not plotted as 0%
+    pub user_rating: i32,               // Rating after the last game of the period
+}
+
+pub struct TrendChartSmoothing {
+    pub rolling_window: usize,
+    pub exponential_smoothing_factor: f32,
+}
+
+impl TrendChartSmoothing {
+    pub fn from_request(request_data: &ChessDataRequest) -> Self {
+        Self {
+            rolling_window: request_data
+                .rolling_window
+                .unwrap_or(DEFAULT_ROLLING_WINDOW)
+                .max(1),
+            exponential_smoothing_factor: request_data
+                .exponential_smoothing_factor
+                .unwrap_or(DEFAULT_EXPONENTIAL_SMOOTHING_FACTOR)
+                .clamp(f32::EPSILON, 1.0),
+        }
+    }
+}
+
+impl Default for TrendChartSmoothing {
+    fn default() -> Self {
+        Self {
+            rolling_window: DEFAULT_ROLLING_WINDOW,
+            exponential_smoothing_factor: DEFAULT_EXPONENTIAL_SMOOTHING_FACTOR,
+        }
+    }
 }
 
-fn get_displayable_game_number(game_number: usize, nb_skipped_games: usize) -> i32 {
-    (game_number + 1 + nb_skipped_games) as i32
+// Skipped games keep their number, so that it matches the one of the games with errors.
+fn get_displayable_game_number(game_info: &GameInfo) -> i32 {
+    (game_info.game_index + 1) as i32
 }
 
 fn get_displayable_game_win_status(game_info: &GameInfo) -> String {
-    if util::has_user_won_game(game_info) {
+    if util::is_game_draw(game_info) {
+        String::from("draw")
+    } else if util::has_user_won_game(game_info) {
         String::from("win")
     } else {
         String::from("loss")
     }
 }
 
+// Lichess streams the most recent games first, so the smoothing is applied from the end
+// of the trend chart data (oldest game) towards its beginning (most recent game).
+fn apply_smoothing(trend_chart_data: &mut [TrendChartDatum], smoothing: &TrendChartSmoothing) {
+    let chronological_differentials = trend_chart_data
+        .iter()
+        .rev()
+        .map(|datum| datum.time_differential)
+        .collect::<Vec<f32>>();
+
+    let mut exponential_average = 0.0;
+    for (i, datum) in trend_chart_data.iter_mut().rev().enumerate() {
+        exponential_average = if i == 0 {
+            datum.time_differential
+        } else {
+            smoothing.exponential_smoothing_factor * datum.time_differential
+                + (1.0 - smoothing.exponential_smoothing_factor) * exponential_average
+        };
+        datum.exponential_average = exponential_average;
+
+        if i + 1 >= smoothing.rolling_window {
+            let window = &chronological_differentials[i + 1 - smoothing.rolling_window..=i];
+            datum.rolling_mean = Some(util::compute_average(window));
+            datum.rolling_median = Some(util::compute_median(window));
+        }
+    }
+}
+
+pub fn compute_trend_line(trend_chart_data: &[TrendChartDatum]) -> Option<TrendLine> {
+    let n_games = trend_chart_data.len();
+    if n_games < 2 {
+        // A line needs at least two points.
+        return None;
+    }
+
+    let xs = (0..n_games).map(|x| x as f32).collect::<Vec<f32>>();
+    let ys = trend_chart_data
+        .iter()
+        .rev()
+        .map(|datum| datum.time_differential)
+        .collect::<Vec<f32>>();
+    let (x_mean, y_mean) = (util::compute_average(&xs), util::compute_average(&ys));
+
+    let covariance: f32 = xs
+        .iter()
+        .zip(ys.iter())
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: f32 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+
+    let slope = covariance / variance;
+    Some(TrendLine {
+        slope,
+        intercept: y_mean - slope * x_mean,
+    })
+}
+
 pub fn generate(
     games: &[GameInfo],
     skipped_games: &HashMap<usize, GameFetchWarning>,
     half_time_differentials: Vec<f32>,
+    smoothing: &TrendChartSmoothing,
 ) -> Vec<TrendChartDatum> {
     let mut trend_chart_data: Vec<TrendChartDatum> = Vec::new();
     let mut game_number_counter: usize = 0;
-    let mut nb_skipped_games: usize = 0;
 
-    for (i, game_info) in games.iter().enumerate() {
-        if skipped_games.contains_key(&i) {
+    for game_info in games.iter() {
+        if util::is_game_skipped(game_info, skipped_games) {
             // The current game has already an internal error.
             // Skip it from the computation.
-            nb_skipped_games += 1;
             continue;
         }
 
         let trend_chart_datum = TrendChartDatum {
             time_differential: half_time_differentials[game_number_counter],
             win_status: get_displayable_game_win_status(game_info),
-            game_number: get_displayable_game_number(game_number_counter, nb_skipped_games),
+            game_number: get_displayable_game_number(game_info),
+            created_at: game_info.created_at,
+            user_rating: game_info.user_rating,
+            rating_diff: game_info.user_rating_diff,
+            rolling_mean: None,
+            rolling_median: None,
+            exponential_average: half_time_differentials[game_number_counter],
         };
 
         trend_chart_data.push(trend_chart_datum);
         game_number_counter += 1;
     }
 
+    apply_smoothing(&mut trend_chart_data, smoothing);
     trend_chart_data
 }
+
+pub fn generate_buckets(
+    trend_chart_data: &[TrendChartDatum],
+    bucket_size: TrendChartBucketSize,
+) -> Vec<TrendChartBucket> {
+    // Games without a timestamp cannot be placed on a date axis.
+    let mut timed_data = trend_chart_data
+        .iter()
+        .filter_map(|datum| datum.created_at.map(|created_at| (created_at, datum)))
+        .collect::<Vec<(u64, &TrendChartDatum)>>();
+    timed_data.sort_by_key(|(created_at, _)| *created_at);
+
+    let mut buckets: Vec<(u64, Vec<&TrendChartDatum>)> = Vec::new();
+    for (created_at, datum) in timed_data {
+        let bucket_start_timestamp = util::get_period_start_timestamp(created_at, bucket_size);
+        match buckets.last_mut() {
+            Some((start, data)) if *start == bucket_start_timestamp => data.push(datum),
+            _ => buckets.push((bucket_start_timestamp, vec![datum])),
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(bucket_start_timestamp, data)| {
+            let time_differentials = data
+                .iter()
+                .map(|datum| datum.time_differential)
+                .collect::<Vec<f32>>();
+            // Draws are left out of the win rate, as in process_win_rate.
+            let decisive_data = data
+                .iter()
+                .filter(|datum| datum.win_status != "draw")
+                .collect::<Vec<_>>();
+            let n_wins = decisive_data
+                .iter()
+                .filter(|datum| datum.win_status == "win")
+                .count();
+            let last_datum = data.last().expect("Buckets are never empty");
+
+            TrendChartBucket {
+                bucket_start: util::format_timestamp_as_utc_date(bucket_start_timestamp),
+                bucket_start_timestamp,
+                games_count: data.len(),
+                average_time_differential: util::compute_average(&time_differentials),
+                win_rate: if decisive_data.is_empty() {
+                    None
+                } else {
+                    Some(n_wins as f32 / decisive_data.len() as f32)
+                },
+                user_rating: last_datum.user_rating + last_datum.rating_diff.unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_trend_chart_data(time_differentials: &[f32]) -> Vec<TrendChartDatum> {
+        time_differentials
+            .iter()
+            .enumerate()
+            .map(|(i, time_differential)| TrendChartDatum {
+                time_differential: *time_differential,
+                win_status: String::from("win"),
+                game_number: i as i32 + 1,
+                created_at: None,
+                user_rating: 1500,
+                rating_diff: None,
+                rolling_mean: None,
+                rolling_median: None,
+                exponential_average: *time_differential,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_smoothing() {
+        // Most recent game first: chronologically the differentials are 1, 2, 9, 4.
+        let mut trend_chart_data = make_trend_chart_data(&[4.0, 9.0, 2.0, 1.0]);
+        let smoothing = TrendChartSmoothing {
+            rolling_window: 3,
+            exponential_smoothing_factor: 0.5,
+        };
+        apply_smoothing(&mut trend_chart_data, &smoothing);
+
+        assert_eq!(trend_chart_data[3].rolling_mean, None);
+        assert_eq!(trend_chart_data[2].rolling_mean, None);
+        assert_eq!(trend_chart_data[1].rolling_mean, Some(4.0));
+        assert_eq!(trend_chart_data[1].rolling_median, Some(2.0));
+        assert_eq!(trend_chart_data[0].rolling_mean, Some(5.0));
+        assert_eq!(trend_chart_data[0].rolling_median, Some(4.0));
+
+        assert_eq!(trend_chart_data[3].exponential_average, 1.0);
+        assert_eq!(trend_chart_data[2].exponential_average, 1.5);
+        assert_eq!(trend_chart_data[1].exponential_average, 5.25);
+        assert_eq!(trend_chart_data[0].exponential_average, 4.625);
+    }
+
+    #[test]
+    fn test_compute_trend_line() {
+        assert_eq!(compute_trend_line(&make_trend_chart_data(&[1.0])), None);
+
+        // Chronologically: 0, 2, 4, 6
+        let trend_line = compute_trend_line(&make_trend_chart_data(&[6.0, 4.0, 2.0, 0.0]));
+        assert_eq!(
+            trend_line,
+            Some(TrendLine {
+                slope: 2.0,
+                intercept: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_generate_buckets() {
+        // 2022-12-30 (friday) and 2023-01-02 (monday), most recent game first.
+        let mut trend_chart_data = make_trend_chart_data(&[4.0, 1.0, 3.0]);
+        trend_chart_data[0].created_at = Some(1672653600000); // 2023-01-02T10:00:00Z
+        trend_chart_data[1].created_at = Some(1672371185802); // 2022-12-30T03:33:05Z
+        trend_chart_data[1].win_status = String::from("loss");
+        trend_chart_data[2].created_at = Some(1672370000000); // 2022-12-30T03:13:20Z
+        trend_chart_data[2].rating_diff = Some(-7);
+
+        let daily_buckets = generate_buckets(&trend_chart_data, TrendChartBucketSize::Day);
+        assert_eq!(daily_buckets.len(), 2);
+        assert_eq!(daily_buckets[0].bucket_start, "2022-12-30");
+        assert_eq!(daily_buckets[0].games_count, 2);
+        assert_eq!(daily_buckets[0].average_time_differential, 2.0);
+        assert_eq!(daily_buckets[0].win_rate, Some(0.5));
+        assert_eq!(daily_buckets[1].bucket_start, "2023-01-02");
+
+        let weekly_buckets = generate_buckets(&trend_chart_data, TrendChartBucketSize::Week);
+        assert_eq!(weekly_buckets.len(), 2);
+        assert_eq!(weekly_buckets[0].bucket_start, "2022-12-26");
+        assert_eq!(weekly_buckets[1].bucket_start, "2023-01-02");
+        assert_eq!(weekly_buckets[1].games_count, 1);
+
+        // A draw is neither a win nor a loss.
+        trend_chart_data[2].win_status = String::from("draw");
+        let daily_buckets = generate_buckets(&trend_chart_data, TrendChartBucketSize::Day);
+        assert_eq!(daily_buckets[0].games_count, 2);
+        assert_eq!(daily_buckets[0].win_rate, Some(0.0));
+
+        // Without decisive games, the period has no win rate rather than a 0% one.
+        trend_chart_data[1].win_status = String::from("draw");
+        let daily_buckets = generate_buckets(&trend_chart_data, TrendChartBucketSize::Day);
+        assert_eq!(daily_buckets[0].games_count, 2);
+        assert_eq!(daily_buckets[0].win_rate, None);
+    }
+}
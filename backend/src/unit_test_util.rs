@@ -39,33 +39,73 @@ pub fn create_mock_game_json(mut info: MinimalGameJsonInfoTesting) -> GameJson {
         }),
         clocks: info.clocks.take(),
         created_at: Some(1672371185802),
+        days_per_turn: None,
         id: Some("9kZXlH2K".to_string()),
         last_move_at: Some(1672371338481),
         moves: info.moves.take(),
         perf: Some("blitz".to_string()),
         players: Some(Players {
             black: Some(PlayerDetail {
+                ai_level: None,
+                berserk: None,
                 rating: Some(2054),
                 rating_diff: None,
                 user: Some(User {
                     id: Some("player1".to_string()),
                     name: info.black_player_name.take(),
+                    title: None,
                 }),
             }),
             white: Some(PlayerDetail {
+                ai_level: None,
+                berserk: None,
                 rating: Some(2000),
                 rating_diff: None,
                 user: Some(User {
                     id: Some("player2".to_string()),
                     name: info.white_player_name.take(),
+                    title: None,
                 }),
             }),
         }),
         rated: Some(true),
         speed: Some("blitz".to_string()),
         status: Some("mate".to_string()),
+        tournament: None,
         variant: Some("standard".to_string()),
         winner: info.winner.take(),
         extra: HashMap::new(),
     }
 }
+
+// A game long enough to be kept in the analysis, played as black by "user". Each white move
+// takes 1 second and each black move 2 seconds.
+pub fn get_some_mocked_complete_game(created_at: u64) -> GameJson {
+    let plies_count = 40;
+    let mut game = create_mock_game_json(MinimalGameJsonInfoTesting {
+        clocks: Some(
+            (0..plies_count)
+                .map(|ply| 18000 - (ply / 2 + 1) * if ply % 2 == 0 { 100 } else { 200 })
+                .collect(),
+        ),
+        moves: Some(vec!["Nf3 Nf6 Ng1 Ng8"; plies_count as usize / 4].join(" ")),
+        black_player_name: Some("user".to_string()),
+        white_player_name: Some("other_user".to_string()),
+        winner: Some("white".to_string()),
+    });
+    game.id = Some(format!("game{}", created_at));
+    game.created_at = Some(created_at);
+    game
+}
+
+// A correspondence game of 40 plies played over 5 days, as black by "user".
+pub fn get_some_mocked_correspondence_game(days_per_turn: u32) -> GameJson {
+    let mut game = get_some_mocked_complete_game(1672371185802);
+    game.clock = None;
+    game.clocks = None;
+    game.days_per_turn = Some(days_per_turn);
+    game.last_move_at = Some(1672371185802 + 5 * 24 * 3_600_000);
+    game.perf = Some("correspondence".to_string());
+    game.speed = Some("correspondence".to_string());
+    game
+}
@@ -1,18 +1,39 @@
 use actix::Addr;
+use chrono::{DateTime, NaiveTime, Utc, Weekday};
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::games_info_generator::GameInfo;
-use crate::service_intermediary::GameFetchWarning;
+use crate::games_info_processor::GamesSummary;
+use crate::service_intermediary::{GameFetchWarning, TrendChartBucketSize};
 use crate::websocket::{WebSocketSession, WebSocketTextMessage};
 
+// Minimum number of games on each side of a comparison, and win rate drop between the compared
+// groups, for an analysis to report a difference.
+pub const MIN_GAMES_FOR_COMPARISON: usize = 3;
+pub const SIGNIFICANT_WIN_RATE_DROP: f32 = 0.1;
+
 pub fn compute_average(times: &[f32]) -> f32 {
-    (times.iter().sum::<f32>() / times.len() as f32) as f32
+    times.iter().sum::<f32>() / times.len() as f32
+}
+
+pub fn compute_median(times: &[f32]) -> f32 {
+    let mut sorted_times = times.to_vec();
+    sorted_times.sort_by(|a, b| a.total_cmp(b));
+
+    let middle = sorted_times.len() / 2;
+    if sorted_times.len().is_multiple_of(2) {
+        (sorted_times[middle - 1] + sorted_times[middle]) / 2.0
+    } else {
+        sorted_times[middle]
+    }
 }
 
 pub fn convert_centiseconds_to_seconds(time: i32) -> f32 {
-    (time as f32 / 100.0) as f32
+    time as f32 / 100.0
 }
 
+#[allow(dead_code)] // Only used for UI testing, see lichess_client.
 pub fn generate_dummy_erros_testing(skipped_games: &mut HashMap<usize, GameFetchWarning>) {
     (0..6).for_each(|i| {
         skipped_games.entry(i).or_insert(if i % 2 == 0 {
@@ -23,28 +44,59 @@ pub fn generate_dummy_erros_testing(skipped_games: &mut HashMap<usize, GameFetch
     });
 }
 
+// Warnings are keyed by the position of the game in the lichess stream, kept in game_index since
+// the games that could not be parsed are missing from the analysed games.
+pub fn is_game_skipped(game: &GameInfo, skipped_games: &HashMap<usize, GameFetchWarning>) -> bool {
+    skipped_games.contains_key(&game.game_index)
+}
+
 pub fn is_game_draw(game: &GameInfo) -> bool {
     game.winner_color.is_none()
 }
 
 pub fn has_user_won_game(game: &GameInfo) -> bool {
-    if game.winner_color.is_some() {
-        return game.user_color == *game.winner_color.as_ref().unwrap();
+    match game.winner_color.as_ref() {
+        Some(winner_color) => game.user_color == *winner_color,
+        None => false,
     }
-    false
 }
 
-pub fn get_game_flagging_information(game: &GameInfo) -> Option<bool> {
-    let times = game
-        .timed_moves
-        .iter()
-        .map(|time_move| time_move.move_time)
-        .collect::<Vec<i64>>();
-
-    if times.len() < 3 {
-        panic!(); // Why did I add this lol?
+// A player who berserked started with half of the clock. The time given up is added back so
+// that both clocks, and the clocks of different games, are compared from the same baseline.
+pub fn get_berserk_adjusted_clocks(
+    game: &GameInfo,
+    user_clock: i64,
+    opponent_clock: i64,
+) -> (i64, i64) {
+    let get_berserk_time = |has_berserked: bool| {
+        if has_berserked {
+            game.berserk_time
+        } else {
+            0
+        }
+    };
+    (
+        user_clock + get_berserk_time(game.user_berserked),
+        opponent_clock + get_berserk_time(game.opponent_berserked),
+    )
+}
+
+pub fn is_significantly_lower(lower: &GamesSummary, reference: &GamesSummary) -> bool {
+    if lower.games_count < MIN_GAMES_FOR_COMPARISON
+        || reference.games_count < MIN_GAMES_FOR_COMPARISON
+    {
+        return false;
     }
 
+    lower
+        .win_rate
+        .zip(reference.win_rate)
+        .is_some_and(|(lower_win_rate, reference_win_rate)| {
+            reference_win_rate - lower_win_rate >= SIGNIFICANT_WIN_RATE_DROP
+        })
+}
+
+pub fn get_game_flagging_information(game: &GameInfo) -> Option<bool> {
     if is_game_draw(game) {
         // No one flagged anyone
         return None;
@@ -67,3 +119,55 @@ pub fn send_websocket_message(
 
     websocket_addr_lock.do_send(message);
 }
+
+// Keeps the "Game x/y" progress prefix so that the loading bar does not reset while waiting.
+pub fn send_websocket_rate_limit_message(
+    websocket_addr: &Addr<WebSocketSession>,
+    games_processed: usize,
+    games_count: &i32,
+    backoff: Duration,
+    retry: u32,
+    max_retries: u32,
+) {
+    let message = WebSocketTextMessage(format!(
+        "Game {}/{} - Rate limited by lichess, retry {}/{} in {} seconds",
+        games_processed,
+        games_count,
+        retry,
+        max_retries,
+        backoff.as_secs()
+    ));
+
+    websocket_addr.do_send(message);
+}
+
+// Timestamps out of the range of chrono fall back to the unix epoch.
+fn get_utc_datetime(timestamp_ms: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(timestamp_ms as i64).unwrap_or_default()
+}
+
+pub fn format_timestamp_as_utc_date(timestamp_ms: u64) -> String {
+    get_utc_datetime(timestamp_ms)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+// Weeks start on monday (ISO 8601).
+pub fn get_period_start_timestamp(timestamp_ms: u64, period: TrendChartBucketSize) -> u64 {
+    let date = get_utc_datetime(timestamp_ms).date_naive();
+    let period_start_date = match period {
+        TrendChartBucketSize::Day => date,
+        TrendChartBucketSize::Week => date.week(Weekday::Mon).first_day(),
+    };
+    period_start_date
+        .and_time(NaiveTime::MIN)
+        .and_utc()
+        .timestamp_millis()
+        .max(0) as u64
+}
+
+pub fn format_timestamp_as_utc_datetime(timestamp_ms: u64) -> String {
+    get_utc_datetime(timestamp_ms)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
@@ -0,0 +1,111 @@
+use crate::service_intermediary::ChessDataRequest;
+
+use serde::{Deserialize, Serialize};
+
+// Lichess variants, named as in the perfType query parameter and the variant field of the games.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Variant {
+    #[default]
+    Standard,
+    Chess960,
+    Crazyhouse,
+    Antichess,
+    Atomic,
+    Horde,
+    KingOfTheHill,
+    RacingKings,
+    ThreeCheck,
+}
+
+impl Variant {
+    // Games without a known variant, e.g from a custom position, are played with standard rules.
+    pub fn from_game_variant(game_variant: Option<&str>) -> Self {
+        game_variant
+            .and_then(|name| serde_json::from_value(serde_json::Value::from(name)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn get_name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    // Lichess rates each variant across every speed, so the game mode only applies to
+    // standard games.
+    pub fn get_perf_type(&self, game_mode: &str) -> String {
+        match self {
+            Variant::Standard => game_mode.to_string(),
+            _ => self.get_name(),
+        }
+    }
+
+    // Below this many plies the half time differential says little about the time management.
+    // Atomic, antichess and racing kings games are decided much sooner than standard ones.
+    pub fn get_min_plies(&self) -> usize {
+        match self {
+            Variant::Standard | Variant::Chess960 | Variant::Horde => 30,
+            Variant::Crazyhouse | Variant::ThreeCheck | Variant::KingOfTheHill => 24,
+            Variant::Atomic | Variant::Antichess | Variant::RacingKings => 16,
+        }
+    }
+
+    // Moves played from memory before the players start thinking, None when the starting
+    // position is shuffled and there is no opening to prepare.
+    pub fn get_opening_moves_count(&self) -> Option<usize> {
+        match self {
+            Variant::Standard | Variant::Horde => Some(10),
+            Variant::Crazyhouse => Some(8),
+            Variant::Antichess
+            | Variant::Atomic
+            | Variant::KingOfTheHill
+            | Variant::RacingKings
+            | Variant::ThreeCheck => Some(6),
+            Variant::Chess960 => None,
+        }
+    }
+}
+
+pub fn get_variant(request_data: &ChessDataRequest) -> Variant {
+    request_data.variant.unwrap_or_default()
+}
+
+// Value of the lichess perfType filter, also used to name the downloaded files.
+pub fn get_perf_type(request_data: &ChessDataRequest) -> String {
+    get_variant(request_data).get_perf_type(&request_data.game_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_game_variant() {
+        assert_eq!(
+            Variant::from_game_variant(Some("standard")),
+            Variant::Standard
+        );
+        assert_eq!(
+            Variant::from_game_variant(Some("chess960")),
+            Variant::Chess960
+        );
+        assert_eq!(
+            Variant::from_game_variant(Some("kingOfTheHill")),
+            Variant::KingOfTheHill
+        );
+        assert_eq!(
+            Variant::from_game_variant(Some("fromPosition")),
+            Variant::Standard
+        );
+        assert_eq!(Variant::from_game_variant(None), Variant::Standard);
+    }
+
+    #[test]
+    fn test_get_perf_type() {
+        assert_eq!(Variant::Standard.get_perf_type("blitz"), "blitz");
+        assert_eq!(Variant::Crazyhouse.get_perf_type("blitz"), "crazyhouse");
+        assert_eq!(Variant::ThreeCheck.get_perf_type("bullet"), "threeCheck");
+    }
+}
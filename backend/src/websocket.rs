@@ -4,13 +4,14 @@ use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use std::time::{Duration, Instant};
 
-use lazy_static::lazy_static;
 use std::sync::Mutex;
 use uuid::Uuid;
 
 // Struct to store the WebSocket session and share across handlers
 pub struct AppState {
     pub websocket_session: Mutex<Option<Addr<WebSocketSession>>>,
+    pub lichess_base_url: String, // Overridden in tests to target the mock lichess server
+    pub database_path: String,    // Overridden in tests to give each test its own database
 }
 
 /// How often heartbeat pings are sent
@@ -27,6 +28,7 @@ pub struct StopWebsocket;
 
 pub struct WebSocketSession {
     heart_beat: Instant,
+    #[allow(dead_code)] // Identifies the session when debugging, nothing reads it yet.
     session_id: String,
     app_state: web::Data<AppState>,
 }
@@ -22,6 +22,8 @@ const GameModeInput: React.FC<GameModeInputProps> = ({ value, onChange }) => (
             <option value="bullet">Bullet</option>
             <option value="blitz">Blitz</option>
             <option value="rapid">Rapid</option>
+            <option value="classical">Classical</option>
+            <option value="correspondence">Correspondence</option>
         </select>
         <div className="border-b border-gray-500 mb-2"></div> {/*This is for the bottom bar*/}
     </div>
@@ -6,7 +6,9 @@ interface LoadingBarProps {
 }
 
 const LoadingBar: React.FC<LoadingBarProps> = ({ progress }) => {
-    const [currentGameStr, totalGamesStr] = progress.replace('Game ', '').split('/');
+    // The backend appends a status after the progress while waiting on lichess, e.g "Game 12/50 - Rate limited...".
+    const [progressStr, statusMessage] = progress.split(' - ');
+    const [currentGameStr, totalGamesStr] = progressStr.replace('Game ', '').split('/');
     const currentGame: number = parseInt(currentGameStr) || 0;
     const totalGames: number | null = parseInt(totalGamesStr) || null;
     const progressValue: number = (currentGame / (totalGames || 1)) * 100; // Divided by 1 to avoid division by zero
@@ -20,6 +22,11 @@ const LoadingBar: React.FC<LoadingBarProps> = ({ progress }) => {
                         <p className="text-sm">Game {currentGame} out of {totalGames} have been loaded</p>
                     )
                 }
+                {
+                    statusMessage && (
+                        <p className="text-sm text-yellow-500">{statusMessage}</p>
+                    )
+                }
             </div>
             <ProgressBar
                 completed={progressValue}